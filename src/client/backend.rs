@@ -0,0 +1,135 @@
+//! HTTP backends of [`super::post_json::PostJsonClient`] and
+//! [`super::json_rpc::JsonRpcClient`].
+
+use super::ClientError;
+use core::time::Duration;
+use http::{Request, Response};
+
+/// HTTP client sending a request and receiving the whole response body.
+///
+/// Implemented for `reqwest`, `hyper_util` legacy client (with `client-hyper`
+/// feature) and any `tower::Service` (with `tower` feature, see
+/// [`TowerBackend`]).
+pub trait HttpBackend: Sync {
+  /// Send a request, `timeout` limits the whole request if the backend
+  /// supports it. Non-success statuses are not errors at this level.
+  fn send(
+    &self,
+    request: Request<Vec<u8>>,
+    timeout: Option<Duration>,
+  ) -> impl Future<Output = Result<Response<Vec<u8>>, ClientError>> + Send;
+}
+
+/// [`HttpBackend`] over [`reqwest::Client`], default.
+#[derive(Debug, Clone, Default)]
+pub struct ReqwestBackend(pub reqwest::Client);
+
+impl HttpBackend for ReqwestBackend {
+  async fn send(
+    &self,
+    request: Request<Vec<u8>>,
+    timeout: Option<Duration>,
+  ) -> Result<Response<Vec<u8>>, ClientError> {
+    let reqwest_err = |err: reqwest::Error| match err.is_timeout() {
+      true => ClientError::Timeout,
+      false => ClientError::Backend(err.into()),
+    };
+    let mut request = reqwest::Request::try_from(request).map_err(reqwest_err)?;
+    *request.timeout_mut() = timeout;
+    let response = self.0.execute(request).await.map_err(reqwest_err)?;
+    let mut builder = Response::builder().status(response.status()).version(response.version());
+    if let Some(headers) = builder.headers_mut() {
+      *headers = response.headers().clone();
+    }
+    let body = response.bytes().await.map_err(reqwest_err)?;
+    builder.body(body.to_vec()).map_err(|err| ClientError::Backend(err.into()))
+  }
+}
+
+#[cfg(feature = "client-hyper")]
+pub use self::hyper_backend::HyperBackend;
+
+#[cfg(feature = "client-hyper")]
+mod hyper_backend {
+  use super::*;
+  use http_body_util::{BodyExt, Full};
+  use hyper::body::Bytes;
+  use hyper_util::client::legacy::{Client, connect::HttpConnector};
+  use hyper_util::rt::TokioExecutor;
+
+  /// [`HttpBackend`] over `hyper_util` legacy client, HTTP only.
+  #[derive(Debug, Clone)]
+  pub struct HyperBackend(pub Client<HttpConnector, Full<Bytes>>);
+
+  impl Default for HyperBackend {
+    fn default() -> Self {
+      HyperBackend(Client::builder(TokioExecutor::new()).build_http())
+    }
+  }
+
+  impl HttpBackend for HyperBackend {
+    async fn send(
+      &self,
+      request: Request<Vec<u8>>,
+      timeout: Option<Duration>,
+    ) -> Result<Response<Vec<u8>>, ClientError> {
+      let response = async {
+        let response = self.0.request(request.map(|body| Full::new(Bytes::from(body)))).await?;
+        let (parts, body) = response.into_parts();
+        let body = body.collect().await?.to_bytes();
+        Ok::<_, Box<dyn std::error::Error + Send + Sync>>(Response::from_parts(
+          parts,
+          body.to_vec(),
+        ))
+      };
+      match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, response)
+          .await
+          .map_err(|_| ClientError::Timeout)?
+          .map_err(ClientError::Backend),
+        None => response.await.map_err(ClientError::Backend),
+      }
+    }
+  }
+}
+
+#[cfg(feature = "tower")]
+pub use self::tower_backend::TowerBackend;
+
+#[cfg(feature = "tower")]
+mod tower_backend {
+  use super::*;
+  use bytes::Bytes;
+  use http_body::Body;
+  use http_body_util::{BodyExt, Full};
+  use tower::{Service, ServiceExt};
+
+  /// [`HttpBackend`] over a user supplied `tower::Service`, e.g. a client
+  /// wrapped into tower middleware.
+  ///
+  /// Per-call timeouts are not supported, use `tower::timeout` instead.
+  #[derive(Debug, Clone)]
+  pub struct TowerBackend<S>(pub S);
+
+  impl<S, B> HttpBackend for TowerBackend<S>
+  where
+    S: Service<Request<Full<Bytes>>, Response = Response<B>> + Clone + Send + Sync,
+    S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    S::Future: Send,
+    B: Body + Send,
+    B::Data: Send,
+    B::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+  {
+    async fn send(
+      &self,
+      request: Request<Vec<u8>>,
+      _timeout: Option<Duration>,
+    ) -> Result<Response<Vec<u8>>, ClientError> {
+      let request = request.map(|body| Full::new(Bytes::from(body)));
+      let response = self.0.clone().oneshot(request).await;
+      let (parts, body) = response.map_err(|err| ClientError::Backend(err.into()))?.into_parts();
+      let body = body.collect().await.map_err(|err| ClientError::Backend(err.into()))?;
+      Ok(Response::from_parts(parts, body.to_bytes().to_vec()))
+    }
+  }
+}
@@ -0,0 +1,347 @@
+//! Utilities for calling an API as a client.
+//!
+//! [`Client`] implements an API over any [`ClientTransport`], see
+//! [`post_json::PostJsonClient`] and [`json_rpc::JsonRpcClient`]. Both of
+//! them send requests with an [`HttpBackend`].
+
+use crate::{HasMethod, ImplsMethod, IsApi, combinator::WithErr};
+use core::{fmt, marker::PhantomData, pin::Pin, time::Duration};
+use http::header::{AUTHORIZATION, HeaderMap, HeaderName, HeaderValue};
+use http::{Request, Response, StatusCode};
+use serde::{Serialize, de::DeserializeOwned};
+use std::sync::Arc;
+
+mod backend;
+
+pub use crate::json_rpc::client as json_rpc;
+pub use crate::post_json::client as post_json;
+use crate::post_json::codec::Encoding;
+pub use crate::post_json::codec::{CodecError, ErrorBody};
+pub use backend::*;
+
+/// Transport of encoded requests, e.g. HTTP, WebSocket, stdio or an
+/// in-memory channel.
+///
+/// Requests and responses are JSON unless `encode` and `decode` are
+/// overridden, e.g. to wrap requests into an envelope.
+pub trait ClientTransport: Sync {
+  /// Encode a request of method `method_name`.
+  fn encode<Req: Serialize>(
+    &self,
+    method_name: &'static str,
+    req: &Req,
+  ) -> Result<Vec<u8>, ClientError> {
+    let _ = method_name;
+    Encoding::Json.encode(req).map_err(ClientError::Codec)
+  }
+
+  /// Send an encoded request and receive an encoded response. Transports
+  /// should respect `options` where applicable.
+  fn send(
+    &self,
+    method_name: &'static str,
+    body: Vec<u8>,
+    options: &CallOptions,
+  ) -> impl Future<Output = Result<Vec<u8>, ClientError>> + Send;
+
+  /// Decode a response of method `method_name`.
+  fn decode<Res: DeserializeOwned>(
+    &self,
+    method_name: &'static str,
+    body: &[u8],
+  ) -> Result<Res, ClientError> {
+    let _ = method_name;
+    Encoding::Json.decode(body).map_err(ClientError::Codec)
+  }
+}
+
+/// Implementor of `WithErr<ClientError, API>` over a [`ClientTransport`].
+pub struct Client<API, T> {
+  transport: T,
+  options: CallOptions,
+  api_marker: PhantomData<fn() -> API>,
+}
+
+impl<API, T: Clone> Clone for Client<API, T> {
+  fn clone(&self) -> Self {
+    Client {
+      transport: self.transport.clone(),
+      options: self.options.clone(),
+      api_marker: PhantomData,
+    }
+  }
+}
+
+impl<API, T: fmt::Debug> fmt::Debug for Client<API, T> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let mut f = f.debug_struct("Client");
+    f.field("transport", &self.transport).field("options", &self.options).finish()
+  }
+}
+
+impl<API, T> Client<API, T> {
+  pub fn from_transport(transport: T) -> Self {
+    Client { transport, options: CallOptions::default(), api_marker: PhantomData }
+  }
+
+  /// Copy of the client which passes `options` with every call.
+  ///
+  /// ```ignore
+  /// let options = CallOptions::new().timeout(Duration::from_secs(1));
+  /// client.with_options(options).call_api(GetA).await?;
+  /// ```
+  pub fn with_options(&self, options: CallOptions) -> Self
+  where
+    T: Clone,
+  {
+    Client { transport: self.transport.clone(), options, api_marker: PhantomData }
+  }
+
+  pub fn transport(&self) -> &T {
+    &self.transport
+  }
+
+  pub fn into_transport(self) -> T {
+    self.transport
+  }
+
+  pub(crate) fn map_transport(self, f: impl FnOnce(T) -> T) -> Self {
+    Client { transport: f(self.transport), ..self }
+  }
+}
+
+impl<API, Req, T> ImplsMethod<WithErr<ClientError, API>, Req> for Client<API, T>
+where
+  API: IsApi + HasMethod<Req>,
+  API::Res: DeserializeOwned,
+  Req: Serialize + Send,
+  T: ClientTransport,
+{
+  async fn call_api(&self, req: Req) -> Result<API::Res, ClientError> {
+    let body = self.transport.encode(API::METHOD_NAME, &req)?;
+    let body = self.transport.send(API::METHOD_NAME, body, &self.options).await?;
+    self.transport.decode(API::METHOD_NAME, &body)
+  }
+}
+
+/// Per-call options, see [`Client::with_options`].
+#[derive(Debug, Clone, Default)]
+pub struct CallOptions {
+  pub(crate) timeout: Option<Duration>,
+  pub(crate) headers: HeaderMap,
+}
+
+impl CallOptions {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Timeout of the whole request.
+  pub fn timeout(self, timeout: Duration) -> Self {
+    Self { timeout: Some(timeout), ..self }
+  }
+
+  /// Extra header, overrides headers set by [`HttpHooks`].
+  pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+    self.headers.insert(name, value);
+    self
+  }
+}
+
+/// Source of bearer tokens for the `Authorization` header.
+pub trait TokenProvider: Send + Sync {
+  /// Get a token. `refresh` is set when the previous token was rejected with
+  /// `401 Unauthorized`, the request is retried once with the new token.
+  fn token(
+    &self,
+    refresh: bool,
+  ) -> Pin<Box<dyn Future<Output = Result<String, ClientError>> + Send + '_>>;
+}
+
+type RequestHook = Arc<dyn Fn(&'static str, &mut HeaderMap) + Send + Sync>;
+
+/// Hooks applied to every request of HTTP clients.
+///
+/// ```ignore
+/// let hooks = HttpHooks::new()
+///   .header(HeaderName::from_static("x-client"), HeaderValue::from_static("cli"))
+///   .token_provider(MyTokens::new())
+///   .on_request(|method_name, headers| { headers.insert("x-request-id", new_request_id()); });
+/// let client = PostJsonClient::new(url, reqwest::Client::new()).unwrap().with_hooks(hooks);
+/// ```
+#[derive(Clone, Default)]
+pub struct HttpHooks {
+  headers: HeaderMap,
+  token_provider: Option<Arc<dyn TokenProvider>>,
+  on_request: Option<RequestHook>,
+}
+
+impl fmt::Debug for HttpHooks {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("HttpHooks")
+      .field("headers", &self.headers)
+      .field("token_provider", &self.token_provider.is_some())
+      .field("on_request", &self.on_request.is_some())
+      .finish()
+  }
+}
+
+impl HttpHooks {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Header sent with every request.
+  pub fn header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+    self.headers.insert(name, value);
+    self
+  }
+
+  /// Set `Authorization: Bearer <token>` for every request.
+  pub fn token_provider(self, provider: impl TokenProvider + 'static) -> Self {
+    Self { token_provider: Some(Arc::new(provider)), ..self }
+  }
+
+  /// Closure called with method name and headers of every request, e.g. to
+  /// set a request id.
+  pub fn on_request(
+    self,
+    f: impl Fn(&'static str, &mut HeaderMap) + Send + Sync + 'static,
+  ) -> Self {
+    Self { on_request: Some(Arc::new(f)), ..self }
+  }
+
+  /// Apply hooks and options to requests built by `mk_request` and send it
+  /// with `backend`. Requests rejected with `401 Unauthorized` are retried
+  /// once with a refreshed token.
+  pub(crate) async fn send(
+    &self,
+    backend: &impl HttpBackend,
+    method_name: &'static str,
+    options: &CallOptions,
+    mk_request: impl Fn() -> Request<Vec<u8>>,
+  ) -> Result<Response<Vec<u8>>, ClientError> {
+    let mut refresh = false;
+    loop {
+      let mut request = mk_request();
+      let headers = request.headers_mut();
+      headers.extend(self.headers.clone());
+      if let Some(provider) = &self.token_provider {
+        let token = provider.token(refresh).await?;
+        let value = HeaderValue::try_from(format!("Bearer {token}"))
+          .map_err(|err| ClientError::Transport(err.into()))?;
+        headers.insert(AUTHORIZATION, value);
+      }
+      if let Some(on_request) = &self.on_request {
+        on_request(method_name, headers);
+      }
+      headers.extend(options.headers.clone());
+      let response = backend.send(request, options.timeout).await?;
+      match response.status() {
+        StatusCode::UNAUTHORIZED if self.token_provider.is_some() && !refresh => refresh = true,
+        status if !status.is_success() => {
+          return Err(ClientError::Status { status, body: error_body(&response) });
+        }
+        _ => return Ok(response),
+      }
+    }
+  }
+}
+
+/// [`ErrorBody`] of a non-success response, if it has one.
+fn error_body(response: &Response<Vec<u8>>) -> Option<ErrorBody> {
+  #[cfg(feature = "post-json-compression")]
+  if let Some(content_encoding) = response.headers().get(http::header::CONTENT_ENCODING) {
+    let content_encoding = content_encoding.to_str().ok()?;
+    let body =
+      crate::post_json::compression::decompress(content_encoding, response.body(), 1 << 16);
+    return serde_json::from_slice(&body.ok()?).ok();
+  }
+  serde_json::from_slice(response.body()).ok()
+}
+
+/// Error of [`Client`] calls.
+#[derive(Debug)]
+pub enum ClientError {
+  /// [`HttpBackend`] failed to send the request or receive the response.
+  Backend(Box<dyn std::error::Error + Send + Sync>),
+  /// Request took longer than [`CallOptions::timeout`].
+  Timeout,
+  /// Server responded with non-success status, `body` is set if the
+  /// response is an [`ErrorBody`], e.g. of `post_json` servers.
+  Status { status: StatusCode, body: Option<ErrorBody> },
+  /// Request or response body could not be encoded or decoded.
+  Codec(CodecError),
+  /// Server responded with an error, e.g. a JsonRPC error object.
+  Rpc { code: i32, message: String },
+  /// Request body could not be compressed or response body could not be
+  /// decompressed.
+  #[cfg(feature = "post-json-compression")]
+  Compression(Box<dyn std::error::Error + Send + Sync>),
+  /// Error of a custom [`ClientTransport`].
+  Transport(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl fmt::Display for ClientError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ClientError::Backend(err) => write!(f, "HTTP error: {err}"),
+      ClientError::Timeout => f.write_str("Request timed out"),
+      ClientError::Status { status, body: Some(body) } => {
+        write!(f, "HTTP status {status}: {}", body.message)
+      }
+      ClientError::Status { status, body: None } => write!(f, "HTTP status {status}"),
+      ClientError::Codec(err) => write!(f, "Codec error: {err}"),
+      ClientError::Rpc { code, message } => write!(f, "RPC error {code}: {message}"),
+      #[cfg(feature = "post-json-compression")]
+      ClientError::Compression(err) => write!(f, "Compression error: {err}"),
+      ClientError::Transport(err) => write!(f, "Transport error: {err}"),
+    }
+  }
+}
+
+impl std::error::Error for ClientError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      ClientError::Backend(err) => Some(err.as_ref()),
+      ClientError::Codec(err) => Some(err),
+      #[cfg(feature = "post-json-compression")]
+      ClientError::Compression(err) => Some(err.as_ref()),
+      ClientError::Transport(err) => Some(err.as_ref()),
+      ClientError::Timeout | ClientError::Status { .. } | ClientError::Rpc { .. } => None,
+    }
+  }
+}
+
+#[cfg(test)]
+#[cfg(feature = "json-rpc-server")]
+mod tests {
+  use super::*;
+  use crate::json_rpc::server::{JsonRpcRequest, json_rpc_router};
+  use crate::test::*;
+
+  /// Calls the backend directly, methods are routed by name.
+  struct InMemory(SomeBackend);
+
+  impl ClientTransport for InMemory {
+    async fn send(
+      &self,
+      method_name: &'static str,
+      body: Vec<u8>,
+      _: &CallOptions,
+    ) -> Result<Vec<u8>, ClientError> {
+      let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+      let req = serde_json::json!({ "method": method_name, "params": { "payload": payload } });
+      let req: JsonRpcRequest = serde_json::from_value(req).unwrap();
+      let res = serde_json::to_value(json_rpc_router::<SomeAPI, _>(&self.0, req).await).unwrap();
+      Ok(serde_json::to_vec(&res["result"]).unwrap())
+    }
+  }
+
+  #[tokio::test]
+  async fn custom_transport() {
+    let client: Client<SomeAPI, _> = Client::from_transport(InMemory(SomeBackend::default()));
+    client.call_api(PostA(true)).await.unwrap().unwrap();
+    assert!(client.call_api(GetA).await.unwrap());
+  }
+}
@@ -0,0 +1,645 @@
+//! Stack combinators across an entire API in reading order.
+
+use super::*;
+use crate::{HasMethod, ImplsMethod};
+use core::marker::PhantomData;
+
+/// Combinator as a layer, wraps both an implementor and its API.
+pub trait ImplLayer {
+  /// API of the wrapped implementor.
+  type Api<API>;
+  /// Wrapped implementor.
+  type Impl<E>;
+
+  fn wrap<E>(&self, implementor: E) -> Self::Impl<E>;
+}
+
+/// Fluent builder of combinator stacks, similar to `tower::ServiceBuilder`.
+/// The first added combinator is the outermost one.
+///
+/// ```ignore
+/// // ApiTracer(cfg, ErrInto::new(IgnoreOk(backend)))
+/// let backend = ImplBuilder::new()
+///   .trace(ApiTracerConfig::default())
+///   .err_into::<MyError>()
+///   .ignore_ok()
+///   .build::<SomeAPI, _>(backend);
+/// ```
+///
+/// The result is [`Built`] which only implements the API computed from the
+/// same stack, e.g. `ErrInto<MyError, IgnoreOk<SomeAPI>>`, see [`BuiltApi`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ImplBuilder<L = Identity>(L);
+
+impl ImplBuilder {
+  pub fn new() -> Self {
+    ImplBuilder(Identity)
+  }
+}
+
+impl<L> ImplBuilder<L> {
+  /// Add a custom layer.
+  pub fn layer<T>(self, layer: T) -> ImplBuilder<Stack<T, L>> {
+    ImplBuilder(Stack { inner: layer, outer: self.0 })
+  }
+
+  /// Add [`IgnoreOk`].
+  pub fn ignore_ok(self) -> ImplBuilder<Stack<IgnoreOkLayer, L>> {
+    self.layer(IgnoreOkLayer)
+  }
+
+  /// Add [`IgnoreRes`].
+  pub fn ignore_res(self) -> ImplBuilder<Stack<IgnoreResLayer, L>> {
+    self.layer(IgnoreResLayer)
+  }
+
+  /// Add [`ErrInto`].
+  pub fn err_into<ErrO>(self) -> ImplBuilder<Stack<ErrIntoLayer<ErrO>, L>> {
+    self.layer(ErrIntoLayer(PhantomData))
+  }
+
+  /// Add [`FlattenErr`].
+  pub fn flatten_err<E>(self) -> ImplBuilder<Stack<FlattenErrLayer<E>, L>> {
+    self.layer(FlattenErrLayer(PhantomData))
+  }
+
+  /// Add [`Cache`], all of the built implementors share the entries.
+  #[cfg(feature = "cache")]
+  pub fn cache(self, config: CacheConfig) -> ImplBuilder<Stack<CacheLayer, L>> {
+    self.layer(CacheLayer(std::sync::Arc::new(config.into())))
+  }
+
+  /// Add [`SingleFlight`] for `methods`, all of the built implementors share
+  /// the calls in flight.
+  #[cfg(all(feature = "cache", feature = "tokio"))]
+  pub fn single_flight<S: Into<String>>(
+    self,
+    methods: impl IntoIterator<Item = S>,
+  ) -> ImplBuilder<Stack<SingleFlightLayer, L>> {
+    self.layer(SingleFlightLayer(std::sync::Arc::new(single_flight::Flights::new(methods))))
+  }
+
+  /// Add [`CircuitBreaker`], all of the built implementors share the
+  /// circuits.
+  pub fn circuit_breaker(
+    self,
+    config: CircuitBreakerConfig,
+  ) -> ImplBuilder<Stack<CircuitBreakerLayer, L>> {
+    self.layer(CircuitBreakerLayer(std::sync::Arc::new(config.into())))
+  }
+
+  /// Add [`Metrics`].
+  pub fn metrics(self, sink: impl MetricsSink + 'static) -> ImplBuilder<Stack<MetricsLayer, L>> {
+    self.layer(MetricsLayer(std::sync::Arc::new(sink)))
+  }
+
+  /// Add [`Fallback`] to `secondary`, which is cloned for each built
+  /// implementor.
+  pub fn fallback<S, F>(
+    self,
+    policy: FallbackPolicy<F>,
+    secondary: S,
+  ) -> ImplBuilder<Stack<FallbackLayer<S, F>, L>> {
+    self.layer(FallbackLayer(std::sync::Arc::new(policy), secondary))
+  }
+
+  /// Add [`ForkAndForget`].
+  #[cfg(feature = "tokio")]
+  pub fn fork_and_forget(self) -> ImplBuilder<Stack<ForkAndForgetLayer, L>> {
+    self.layer(ForkAndForgetLayer)
+  }
+
+  /// Add [`ConcurrencyLimit`], all of the built implementors share the
+  /// limits.
+  #[cfg(feature = "tokio")]
+  pub fn concurrency_limit(
+    self,
+    config: ConcurrencyLimitConfig,
+  ) -> ImplBuilder<Stack<ConcurrencyLimitLayer, L>> {
+    self.layer(ConcurrencyLimitLayer(std::sync::Arc::new(config.into())))
+  }
+
+  /// Add [`RateLimit`] with [`ContextKey`], all of the built implementors
+  /// share the buckets.
+  #[cfg(feature = "tokio")]
+  pub fn rate_limit(self, config: RateLimitConfig) -> ImplBuilder<Stack<RateLimitLayer, L>> {
+    self.layer(RateLimitLayer(std::sync::Arc::new(config.into())))
+  }
+
+  /// Add [`Retry`].
+  #[cfg(feature = "tokio")]
+  pub fn retry<P>(self, policy: P) -> ImplBuilder<Stack<RetryLayer<P>, L>> {
+    self.layer(RetryLayer(policy))
+  }
+
+  /// Add [`Timeout`].
+  #[cfg(feature = "tokio")]
+  pub fn timeout(self, config: TimeoutConfig) -> ImplBuilder<Stack<TimeoutLayer, L>> {
+    self.layer(TimeoutLayer(config))
+  }
+
+  /// Add [`tracing::ApiTracer`].
+  #[cfg(feature = "tracing")]
+  pub fn trace<F, G>(
+    self,
+    config: tracing::ApiTracerConfig<F, G>,
+  ) -> ImplBuilder<Stack<ApiTracerLayer<F, G>, L>> {
+    self.layer(ApiTracerLayer(config))
+  }
+
+  /// Wrap `implementor` of `API` with all layers.
+  pub fn build<API, E>(&self, implementor: E) -> Built<L::Api<API>, L::Impl<E>>
+  where
+    L: ImplLayer,
+  {
+    Built::new(self.0.wrap(implementor))
+  }
+}
+
+/// API of implementors built by [`ImplBuilder<L>`].
+pub type BuiltApi<L, API> = <L as ImplLayer>::Api<API>;
+
+/// Implementor built by [`ImplBuilder`], implements only `API`.
+///
+/// **Implementor** combinator.
+pub struct Built<API, E> {
+  implementor: E,
+  api_marker: PhantomData<fn() -> API>,
+}
+
+impl<API, E> Built<API, E> {
+  fn new(implementor: E) -> Self {
+    Self { implementor, api_marker: PhantomData }
+  }
+
+  pub fn into_inner(self) -> E {
+    self.implementor
+  }
+}
+
+impl<API, E: Clone> Clone for Built<API, E> {
+  fn clone(&self) -> Self {
+    Self::new(self.implementor.clone())
+  }
+}
+
+impl<API, E, M> ImplsMethod<API, M> for Built<API, E>
+where
+  API: HasMethod<M>,
+  E: ImplsMethod<API, M> + Sync,
+  M: Send,
+{
+  async fn call_api(&self, req: M) -> API::Res {
+    self.implementor.call_api(req).await
+  }
+}
+
+/// Layer that does nothing.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Identity;
+
+impl ImplLayer for Identity {
+  type Api<API> = API;
+  type Impl<E> = E;
+
+  fn wrap<E>(&self, implementor: E) -> E {
+    implementor
+  }
+}
+
+/// Two layers, `outer` wraps `inner`.
+#[derive(Debug, Clone, Copy)]
+pub struct Stack<Inner, Outer> {
+  inner: Inner,
+  outer: Outer,
+}
+
+impl<Inner: ImplLayer, Outer: ImplLayer> ImplLayer for Stack<Inner, Outer> {
+  type Api<API> = Outer::Api<Inner::Api<API>>;
+  type Impl<E> = Outer::Impl<Inner::Impl<E>>;
+
+  fn wrap<E>(&self, implementor: E) -> Self::Impl<E> {
+    self.outer.wrap(self.inner.wrap(implementor))
+  }
+}
+
+/// Layer of [`IgnoreOk`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IgnoreOkLayer;
+
+impl ImplLayer for IgnoreOkLayer {
+  type Api<API> = IgnoreOk<API>;
+  type Impl<E> = IgnoreOk<E>;
+
+  fn wrap<E>(&self, implementor: E) -> IgnoreOk<E> {
+    IgnoreOk(implementor)
+  }
+}
+
+/// Layer of [`IgnoreRes`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IgnoreResLayer;
+
+impl ImplLayer for IgnoreResLayer {
+  type Api<API> = IgnoreRes<API>;
+  type Impl<E> = IgnoreRes<E>;
+
+  fn wrap<E>(&self, implementor: E) -> IgnoreRes<E> {
+    IgnoreRes(implementor)
+  }
+}
+
+/// Layer of [`ErrInto`].
+pub struct ErrIntoLayer<ErrO>(PhantomData<fn() -> ErrO>);
+
+impl<ErrO> ImplLayer for ErrIntoLayer<ErrO> {
+  type Api<API> = ErrInto<ErrO, API>;
+  type Impl<E> = ErrInto<ErrO, E>;
+
+  fn wrap<E>(&self, implementor: E) -> ErrInto<ErrO, E> {
+    ErrInto::new(implementor)
+  }
+}
+
+/// Layer of [`FlattenErr`].
+pub struct FlattenErrLayer<Err>(PhantomData<fn() -> Err>);
+
+impl<Err> ImplLayer for FlattenErrLayer<Err> {
+  type Api<API> = FlattenErr<Err, API>;
+  type Impl<E> = FlattenErr<Err, E>;
+
+  fn wrap<E>(&self, implementor: E) -> FlattenErr<Err, E> {
+    FlattenErr::new(implementor)
+  }
+}
+
+/// Layer of [`Cache`].
+#[cfg(feature = "cache")]
+#[derive(Debug, Clone)]
+pub struct CacheLayer(std::sync::Arc<cache::Store>);
+
+#[cfg(feature = "cache")]
+impl ImplLayer for CacheLayer {
+  type Api<API> = API;
+  type Impl<E> = Cache<E>;
+
+  fn wrap<E>(&self, implementor: E) -> Cache<E> {
+    Cache::with_store(self.0.clone(), implementor)
+  }
+}
+
+/// Layer of [`SingleFlight`].
+#[cfg(all(feature = "cache", feature = "tokio"))]
+#[derive(Debug, Clone)]
+pub struct SingleFlightLayer(std::sync::Arc<single_flight::Flights>);
+
+#[cfg(all(feature = "cache", feature = "tokio"))]
+impl ImplLayer for SingleFlightLayer {
+  type Api<API> = API;
+  type Impl<E> = SingleFlight<E>;
+
+  fn wrap<E>(&self, implementor: E) -> SingleFlight<E> {
+    SingleFlight::with_flights(self.0.clone(), implementor)
+  }
+}
+
+/// Layer of [`CircuitBreaker`].
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerLayer(std::sync::Arc<circuit_breaker::Circuits>);
+
+impl ImplLayer for CircuitBreakerLayer {
+  type Api<API> = WithCircuitBreaker<API>;
+  type Impl<E> = CircuitBreaker<E>;
+
+  fn wrap<E>(&self, implementor: E) -> CircuitBreaker<E> {
+    CircuitBreaker::with_circuits(self.0.clone(), implementor)
+  }
+}
+
+/// Layer of [`Metrics`].
+#[derive(Clone)]
+pub struct MetricsLayer(std::sync::Arc<dyn MetricsSink>);
+
+impl ImplLayer for MetricsLayer {
+  type Api<API> = API;
+  type Impl<E> = Metrics<E>;
+
+  fn wrap<E>(&self, implementor: E) -> Metrics<E> {
+    Metrics::with_sink(self.0.clone(), implementor)
+  }
+}
+
+/// Layer of [`Fallback`].
+#[derive(Debug, Clone)]
+pub struct FallbackLayer<S, F = AllErrors>(std::sync::Arc<FallbackPolicy<F>>, S);
+
+impl<S: Clone, F> ImplLayer for FallbackLayer<S, F> {
+  type Api<API> = API;
+  type Impl<E> = Fallback<E, S, F>;
+
+  fn wrap<E>(&self, implementor: E) -> Fallback<E, S, F> {
+    Fallback::with_policy(self.0.clone(), implementor, self.1.clone())
+  }
+}
+
+/// Layer of [`ForkAndForget`].
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ForkAndForgetLayer;
+
+#[cfg(feature = "tokio")]
+impl ImplLayer for ForkAndForgetLayer {
+  type Api<API> = API;
+  type Impl<E> = ForkAndForget<E>;
+
+  fn wrap<E>(&self, implementor: E) -> ForkAndForget<E> {
+    ForkAndForget(implementor)
+  }
+}
+
+/// Layer of [`ConcurrencyLimit`].
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone)]
+pub struct ConcurrencyLimitLayer(std::sync::Arc<concurrency_limit::Limits>);
+
+#[cfg(feature = "tokio")]
+impl ImplLayer for ConcurrencyLimitLayer {
+  type Api<API> = WithOverload<API>;
+  type Impl<E> = ConcurrencyLimit<E>;
+
+  fn wrap<E>(&self, implementor: E) -> ConcurrencyLimit<E> {
+    ConcurrencyLimit::with_limits(self.0.clone(), implementor)
+  }
+}
+
+/// Layer of [`RateLimit`].
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone)]
+pub struct RateLimitLayer(std::sync::Arc<rate_limit::Buckets>);
+
+#[cfg(feature = "tokio")]
+impl ImplLayer for RateLimitLayer {
+  type Api<API> = WithRateLimit<API>;
+  type Impl<E> = RateLimit<E>;
+
+  fn wrap<E>(&self, implementor: E) -> RateLimit<E> {
+    RateLimit::with_buckets(self.0.clone(), ContextKey, implementor)
+  }
+}
+
+/// Layer of [`Retry`].
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone)]
+pub struct RetryLayer<P>(pub P);
+
+#[cfg(feature = "tokio")]
+impl<P: Clone> ImplLayer for RetryLayer<P> {
+  type Api<API> = API;
+  type Impl<E> = Retry<P, E>;
+
+  fn wrap<E>(&self, implementor: E) -> Retry<P, E> {
+    Retry(self.0.clone(), implementor)
+  }
+}
+
+/// Layer of [`Timeout`].
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone)]
+pub struct TimeoutLayer(pub TimeoutConfig);
+
+#[cfg(feature = "tokio")]
+impl ImplLayer for TimeoutLayer {
+  type Api<API> = WithTimeout<API>;
+  type Impl<E> = Timeout<E>;
+
+  fn wrap<E>(&self, implementor: E) -> Timeout<E> {
+    Timeout(self.0.clone(), implementor)
+  }
+}
+
+/// Layer of [`tracing::ApiTracer`].
+#[cfg(feature = "tracing")]
+#[derive(Debug, Clone, Copy)]
+pub struct ApiTracerLayer<F = tracing::DebugFormat, G = tracing::DebugFormat>(
+  pub tracing::ApiTracerConfig<F, G>,
+);
+
+#[cfg(feature = "tracing")]
+impl<F, G> ImplLayer for ApiTracerLayer<F, G> {
+  type Api<API> = API;
+  type Impl<E> = tracing::ApiTracer<E, F, G>;
+
+  fn wrap<E>(&self, implementor: E) -> tracing::ApiTracer<E, F, G> {
+    tracing::ApiTracer(self.0, implementor)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::func::FnImpl;
+  use crate::test::*;
+
+  #[tokio::test]
+  async fn builder() {
+    #[derive(Debug, PartialEq)]
+    struct MyError(String);
+    impl From<String> for MyError {
+      fn from(err: String) -> Self {
+        MyError(err)
+      }
+    }
+
+    let builder = ImplBuilder::new().err_into::<MyError>().ignore_ok();
+    let backend: Built<ErrInto<MyError, IgnoreOk<SomeAPI>>, _> =
+      builder.build::<SomeAPI, _>(SomeBackend::default());
+    assert_eq!(backend.call_api(PostA(true)).await, Ok(()));
+    assert!(matches!(backend.call_api(PostA(true)).await, Err(MyError(_))));
+
+    let backend: Built<IgnoreRes<SomeAPI>, _> =
+      ImplBuilder::new().ignore_res().build(SomeBackend::default());
+    let () = backend.call_api(GetA).await;
+  }
+
+  #[cfg(feature = "tokio")]
+  #[tokio::test]
+  async fn retry() {
+    use core::time::Duration;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    let calls = Arc::new(AtomicU32::new(0));
+    let backend = FnImpl::<SomeAPI>::builder()
+      .on(|GetA| async { true })
+      .on({
+        let calls = calls.clone();
+        move |PostA(_)| {
+          let n = calls.fetch_add(1, Ordering::SeqCst);
+          async move { if n == 0 { Err("down".to_owned()) } else { Ok(()) } }
+        }
+      })
+      .build();
+    let backoff = Backoff { initial: Duration::from_millis(1), ..Default::default() };
+    let policy = RetryPolicy::new().backoff(backoff).idempotent::<SomeAPI, PostA>();
+    let backend: Built<SomeAPI, _> = ImplBuilder::new().retry(policy).build(backend);
+    backend.call_api(PostA(true)).await.unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+  }
+
+  #[cfg(feature = "tokio")]
+  #[tokio::test]
+  async fn timeout() {
+    use core::time::Duration;
+
+    let backend = FnImpl::<SomeAPI>::builder()
+      .on(|GetA| async { true })
+      .on(|PostA(_)| async {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        Ok(())
+      })
+      .build();
+    let config = TimeoutConfig::new().timeout(Duration::from_millis(20));
+    let backend: Built<WithTimeout<SomeAPI>, _> = ImplBuilder::new().timeout(config).build(backend);
+    let res = backend.call_api(PostA(true)).await;
+    assert_eq!(res, Err(TimeoutError::TimedOut(Duration::from_millis(20))));
+  }
+
+  #[cfg(feature = "tokio")]
+  #[tokio::test]
+  async fn concurrency_limit() {
+    use std::sync::Arc;
+    use tokio::sync::Notify;
+
+    let (started, done) = (Arc::new(Notify::new()), Arc::new(Notify::new()));
+    let backend = FnImpl::<SomeAPI>::builder()
+      .on(|GetA| async { true })
+      .on({
+        let (started, done) = (started.clone(), done.clone());
+        move |PostA(_)| {
+          let (started, done) = (started.clone(), done.clone());
+          async move {
+            started.notify_one();
+            done.notified().await;
+            Ok(())
+          }
+        }
+      })
+      .build();
+    let config = ConcurrencyLimitConfig::new().limit(1).mode(LimitMode::Shed);
+    let builder = ImplBuilder::new().concurrency_limit(config);
+    let running: Built<WithOverload<SomeAPI>, _> = builder.clone().build(backend.clone());
+    let shed: Built<WithOverload<SomeAPI>, _> = builder.build(backend);
+    let running = tokio::spawn(async move { running.call_api(PostA(true)).await });
+    started.notified().await;
+    assert_eq!(shed.call_api(PostA(true)).await, Err(OverloadError::Overloaded));
+    done.notify_one();
+    running.await.unwrap().unwrap();
+  }
+
+  #[cfg(feature = "tokio")]
+  #[tokio::test]
+  async fn rate_limit() {
+    let builder = ImplBuilder::new().rate_limit(RateLimitConfig::new().limit(Quota::per_minute(1)));
+    let backend: Built<WithRateLimit<SomeAPI>, _> = builder.clone().build(SomeBackend::default());
+    backend.call_api(PostA(true)).await.unwrap();
+    let limited: Built<WithRateLimit<SomeAPI>, _> = builder.build(SomeBackend::default());
+    let res = limited.call_api(PostA(true)).await;
+    assert!(matches!(res, Err(RateLimitError::RateLimited { .. })));
+  }
+
+  #[tokio::test]
+  async fn circuit_breaker() {
+    let config = CircuitBreakerConfig::new().min_calls(1);
+    let backend: Built<WithCircuitBreaker<SomeAPI>, _> =
+      ImplBuilder::new().circuit_breaker(config).build(SomeBackend::default());
+    backend.call_api(PostA(true)).await.unwrap();
+    assert!(matches!(backend.call_api(PostA(true)).await, Err(CircuitBreakerError::Err(_))));
+    assert_eq!(backend.call_api(PostA(true)).await, Err(CircuitBreakerError::CircuitOpen));
+  }
+
+  #[cfg(feature = "cache")]
+  #[tokio::test]
+  async fn cache() {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    let calls = Arc::new(AtomicU32::new(0));
+    let backend = FnImpl::<SomeAPI>::builder()
+      .on({
+        let calls = calls.clone();
+        move |GetA| {
+          calls.fetch_add(1, Ordering::SeqCst);
+          async { true }
+        }
+      })
+      .on(|PostA(_)| async { Ok(()) })
+      .build();
+    let builder = ImplBuilder::new().cache(CacheConfig::new().method("get_a"));
+    let a: Built<SomeAPI, _> = builder.clone().build(backend.clone());
+    let b: Built<SomeAPI, _> = builder.build(backend);
+    assert!(a.call_api(GetA).await && b.call_api(GetA).await);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+  }
+
+  #[cfg(all(feature = "cache", feature = "tokio"))]
+  #[tokio::test]
+  async fn single_flight() {
+    use core::time::Duration;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    let calls = Arc::new(AtomicU32::new(0));
+    let backend = FnImpl::<SomeAPI>::builder()
+      .on({
+        let calls = calls.clone();
+        move |GetA| {
+          calls.fetch_add(1, Ordering::SeqCst);
+          async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            true
+          }
+        }
+      })
+      .on(|PostA(_)| async { Ok(()) })
+      .build();
+    let builder = ImplBuilder::new().single_flight(["get_a"]);
+    let a: Built<SomeAPI, _> = builder.clone().build(backend.clone());
+    let b: Built<SomeAPI, _> = builder.build(backend);
+    assert_eq!(tokio::join!(a.call_api(GetA), b.call_api(GetA)), (true, true));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+  }
+
+  #[tokio::test]
+  async fn metrics() {
+    let registry = MetricsRegistry::new();
+    let backend: Built<SomeAPI, _> =
+      ImplBuilder::new().metrics(registry.clone()).build(SomeBackend::default());
+    backend.call_api(PostA(true)).await.unwrap();
+    assert!(backend.call_api(PostA(true)).await.is_err());
+    let rendered = registry.render();
+    assert!(rendered.contains(r#"aisil_calls_total{api="SomeAPI",method="post_a"} 2"#));
+    assert!(rendered.contains(r#"aisil_errors_total{api="SomeAPI",method="post_a"} 1"#));
+  }
+
+  #[cfg(feature = "tracing")]
+  #[tokio::test]
+  async fn trace() {
+    let config = tracing::ApiTracerConfig::new().display_errors();
+    let backend: Built<SomeAPI, _> = ImplBuilder::new().trace(config).build(SomeBackend::default());
+    backend.call_api(PostA(true)).await.unwrap();
+    assert!(backend.call_api(GetA).await);
+  }
+
+  #[tokio::test]
+  async fn fallback() {
+    let primary = FnImpl::<SomeAPI>::builder()
+      .on(|GetA| async { false })
+      .on(|PostA(_)| async { Err("down".to_owned()) })
+      .build();
+    let policy = FallbackPolicy::new().method("post_a");
+    let backend: Built<SomeAPI, _> =
+      ImplBuilder::new().fallback(policy, SomeBackend::default()).build(primary);
+    backend.call_api(PostA(true)).await.unwrap();
+    assert!(!backend.call_api(GetA).await);
+  }
+}
@@ -0,0 +1,377 @@
+//! Memoize responses of read methods.
+
+use super::Outcome;
+use crate::{HasMethod, ImplsMethod, IsApi};
+use core::any::{Any, TypeId};
+use core::time::Duration;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Caches responses of the methods enabled in [`CacheConfig`], keyed by the
+/// request and response types, the method name and the request serialized to
+/// JSON. Error responses according to [`Outcome`] are only cached with
+/// [`CacheConfig::cache_errors`].
+///
+/// Entries expire after TTL, least recently used entries are evicted when
+/// [`CacheConfig::max_entries`] is reached. Calls of write methods can
+/// invalidate entries with [`CacheConfig::invalidate_on`], or use
+/// [`Cache::invalidate`] and similar.
+///
+/// Clones share the same entries.
+///
+/// ```ignore
+/// let config = CacheConfig::new()
+///   .method_ttl("get_a", Duration::from_secs(5))
+///   .invalidate_on("post_a", "get_a");
+/// let backend = Cache::new(config, backend);
+/// ```
+///
+/// **Implementor** combinator.
+#[derive(Debug, Clone)]
+pub struct Cache<B> {
+  store: Arc<Store>,
+  inner: B,
+}
+
+impl<B> Cache<B> {
+  pub fn new(config: CacheConfig, inner: B) -> Self {
+    Self::with_store(Arc::new(Store::from(config)), inner)
+  }
+
+  pub(crate) fn with_store(store: Arc<Store>, inner: B) -> Self {
+    Self { store, inner }
+  }
+
+  /// Remove all of the entries of a method.
+  pub fn invalidate(&self, method_name: &str) {
+    self.store.lock().retain(|(_, _, name, _)| *name != method_name);
+  }
+
+  /// Remove the entry of a single request.
+  pub fn invalidate_request<API, M>(&self, req: &M)
+  where
+    API: HasMethod<M, Res: 'static>,
+    M: Serialize + 'static,
+  {
+    if let Some(key) = key::<API, M>(req) {
+      self.store.lock().retain(|other| *other != key);
+    }
+  }
+
+  pub fn invalidate_all(&self) {
+    self.store.lock().retain(|_| false);
+  }
+
+  /// Number of cached entries, including expired ones.
+  pub fn len(&self) -> usize {
+    self.store.lock().entries.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  pub fn into_inner(self) -> B {
+    self.inner
+  }
+}
+
+/// Parameters of [`Cache`]. No method is cached by default.
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+  /// Time to live of entries, default: 60s.
+  pub ttl: Duration,
+  /// Maximum number of entries of all of the methods, default: `1024`.
+  pub max_entries: usize,
+  /// Names of cached methods with optional overrides of
+  /// [`CacheConfig::ttl`].
+  pub methods: HashMap<String, Option<Duration>>,
+  /// Names of methods to invalidate by the name of the called method.
+  pub invalidations: HashMap<String, Vec<String>>,
+  /// Cache error responses too, default: `false`.
+  pub cache_errors: bool,
+}
+
+impl Default for CacheConfig {
+  fn default() -> Self {
+    Self {
+      ttl: Duration::from_secs(60),
+      max_entries: 1024,
+      methods: HashMap::new(),
+      invalidations: HashMap::new(),
+      cache_errors: false,
+    }
+  }
+}
+
+impl CacheConfig {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn ttl(self, ttl: Duration) -> Self {
+    Self { ttl, ..self }
+  }
+
+  pub fn max_entries(self, max_entries: usize) -> Self {
+    Self { max_entries, ..self }
+  }
+
+  /// Cache method by its name.
+  pub fn method(mut self, method_name: impl Into<String>) -> Self {
+    self.methods.insert(method_name.into(), None);
+    self
+  }
+
+  /// Cache method by its name with a custom TTL.
+  pub fn method_ttl(mut self, method_name: impl Into<String>, ttl: Duration) -> Self {
+    self.methods.insert(method_name.into(), Some(ttl));
+    self
+  }
+
+  /// Invalidate entries of `cached` method after each call of `write`.
+  pub fn invalidate_on(mut self, write: impl Into<String>, cached: impl Into<String>) -> Self {
+    self.invalidations.entry(write.into()).or_default().push(cached.into());
+    self
+  }
+
+  pub fn cache_errors(self, cache_errors: bool) -> Self {
+    Self { cache_errors, ..self }
+  }
+
+  fn ttl_of(&self, method_name: &str) -> Option<Duration> {
+    self.methods.get(method_name).map(|ttl| ttl.unwrap_or(self.ttl))
+  }
+}
+
+/// Request type, response type, method name and request serialized to JSON.
+pub(crate) type Key = (TypeId, TypeId, &'static str, Vec<u8>);
+
+/// Key of `req`, `None` if it doesn't serialize.
+pub(crate) fn key<API, M>(req: &M) -> Option<Key>
+where
+  API: HasMethod<M, Res: 'static>,
+  M: Serialize + 'static,
+{
+  let req = serde_json::to_vec(req).ok()?;
+  Some((TypeId::of::<M>(), TypeId::of::<API::Res>(), API::METHOD_NAME, req))
+}
+
+#[derive(Debug)]
+pub(crate) struct Store {
+  config: CacheConfig,
+  state: Mutex<State>,
+}
+
+impl From<CacheConfig> for Store {
+  fn from(config: CacheConfig) -> Self {
+    Self { config, state: Mutex::default() }
+  }
+}
+
+impl Store {
+  fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+    self.state.lock().unwrap_or_else(|err| err.into_inner())
+  }
+}
+
+#[derive(Debug, Default)]
+struct State {
+  entries: HashMap<Key, Entry>,
+  /// Keys by the last use.
+  recency: BTreeMap<u64, Key>,
+  tick: u64,
+  /// Incremented by each invalidation, so that calls started before it
+  /// don't store stale responses.
+  generation: u64,
+}
+
+#[derive(Debug)]
+struct Entry {
+  res: Box<dyn Any + Send + Sync>,
+  expires: Instant,
+  used: u64,
+}
+
+impl State {
+  fn get<T: Clone + 'static>(&mut self, key: &Key, now: Instant) -> Option<T> {
+    let entry = self.entries.get_mut(key)?;
+    if entry.expires <= now {
+      self.recency.remove(&entry.used);
+      self.entries.remove(key);
+      return None;
+    }
+    self.tick += 1;
+    let key = self.recency.remove(&entry.used)?;
+    self.recency.insert(self.tick, key);
+    entry.used = self.tick;
+    entry.res.downcast_ref().cloned()
+  }
+
+  fn insert(&mut self, key: Key, res: Box<dyn Any + Send + Sync>, expires: Instant, max: usize) {
+    if let Some(entry) = self.entries.remove(&key) {
+      self.recency.remove(&entry.used);
+    }
+    while self.entries.len() >= max.max(1) {
+      let Some((_, key)) = self.recency.pop_first() else { break };
+      self.entries.remove(&key);
+    }
+    self.tick += 1;
+    self.recency.insert(self.tick, key.clone());
+    self.entries.insert(key, Entry { res, expires, used: self.tick });
+  }
+
+  fn retain(&mut self, mut f: impl FnMut(&Key) -> bool) {
+    self.generation += 1;
+    self.entries.retain(|key, _| f(key));
+    self.recency.retain(|_, key| f(key));
+  }
+}
+
+impl<API, M, B> ImplsMethod<API, M> for Cache<B>
+where
+  API: IsApi + HasMethod<M>,
+  API::Res: Outcome + Clone + Send + Sync + 'static,
+  B: ImplsMethod<API, M> + Sync,
+  M: Serialize + Send + 'static,
+{
+  async fn call_api(&self, req: M) -> API::Res {
+    let store = &self.store;
+    let cached = store.config.ttl_of(API::METHOD_NAME).zip(key::<API, M>(&req));
+    let Some((ttl, key)) = cached else {
+      let res = self.inner.call_api(req).await;
+      for method_name in store.config.invalidations.get(API::METHOD_NAME).into_iter().flatten() {
+        self.invalidate(method_name);
+      }
+      return res;
+    };
+    let generation = {
+      let mut state = store.lock();
+      if let Some(res) = state.get(&key, Instant::now()) {
+        return res;
+      }
+      state.generation
+    };
+    let res = self.inner.call_api(req).await;
+    if res.is_error() && !store.config.cache_errors {
+      return res;
+    }
+    let mut state = store.lock();
+    if state.generation == generation {
+      let expires = Instant::now() + ttl;
+      state.insert(key, Box::new(res.clone()), expires, store.config.max_entries);
+    }
+    res
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::func::FnImpl;
+  use crate::test::*;
+  use std::sync::atomic::{AtomicU32, Ordering};
+
+  #[tokio::test]
+  async fn cache() {
+    let calls = Arc::new(AtomicU32::new(0));
+    let backend = FnImpl::<SomeAPI>::builder()
+      .on({
+        let calls = calls.clone();
+        move |GetA| {
+          calls.fetch_add(1, Ordering::SeqCst);
+          async { true }
+        }
+      })
+      .on({
+        let calls = calls.clone();
+        move |PostA(_)| {
+          calls.fetch_add(1, Ordering::SeqCst);
+          async { Ok(()) }
+        }
+      })
+      .build();
+    let calls = || calls.swap(0, Ordering::SeqCst);
+
+    let config = CacheConfig::new().method("get_a").invalidate_on("post_a", "get_a");
+    let cache = Cache::new(config, backend.clone());
+    assert!(cache.call_api(GetA).await);
+    assert!(cache.clone().call_api(GetA).await);
+    assert_eq!((calls(), cache.len()), (1, 1));
+    cache.call_api(PostA(true)).await.unwrap();
+    cache.call_api(PostA(true)).await.unwrap();
+    assert_eq!((calls(), cache.len()), (2, 0));
+    cache.call_api(GetA).await;
+    cache.invalidate_request::<SomeAPI, _>(&GetA);
+    cache.call_api(GetA).await;
+    assert_eq!(calls(), 2);
+
+    let config = CacheConfig::new().method_ttl("post_a", Duration::from_millis(20)).max_entries(1);
+    let cache = Cache::new(config, backend);
+    cache.call_api(PostA(true)).await.unwrap();
+    cache.call_api(PostA(true)).await.unwrap();
+    cache.call_api(PostA(false)).await.unwrap();
+    cache.call_api(PostA(true)).await.unwrap();
+    assert_eq!((calls(), cache.len()), (3, 1));
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    cache.call_api(PostA(true)).await.unwrap();
+    assert_eq!(calls(), 1);
+  }
+
+  #[tokio::test]
+  async fn cache_errors() {
+    let calls = Arc::new(AtomicU32::new(0));
+    let backend = FnImpl::<SomeAPI>::builder()
+      .on(|GetA| async { true })
+      .on({
+        let calls = calls.clone();
+        move |PostA(_)| {
+          calls.fetch_add(1, Ordering::SeqCst);
+          async { Err("down".to_owned()) }
+        }
+      })
+      .build();
+    let calls = || calls.swap(0, Ordering::SeqCst);
+
+    let cache = Cache::new(CacheConfig::new().method("post_a"), backend.clone());
+    assert!(cache.call_api(PostA(true)).await.is_err());
+    assert!(cache.call_api(PostA(true)).await.is_err());
+    assert_eq!((calls(), cache.len()), (2, 0));
+
+    let cache = Cache::new(CacheConfig::new().method("post_a").cache_errors(true), backend);
+    assert!(cache.call_api(PostA(true)).await.is_err());
+    assert!(cache.call_api(PostA(true)).await.is_err());
+    assert_eq!((calls(), cache.len()), (1, 1));
+  }
+
+  #[tokio::test]
+  async fn equal_requests_of_different_apis() {
+    use crate::CallApi;
+
+    struct OtherAPI;
+    crate::define_api! { OtherAPI => {
+      "get_a", GetA => u32;
+    } }
+
+    struct Backend;
+    impl ImplsMethod<SomeAPI, GetA> for Backend {
+      async fn call_api(&self, _: GetA) -> bool {
+        true
+      }
+    }
+    impl ImplsMethod<OtherAPI, GetA> for Backend {
+      async fn call_api(&self, _: GetA) -> u32 {
+        1
+      }
+    }
+
+    let cache = Cache::new(CacheConfig::new().method("get_a"), Backend);
+    for _ in 0..2 {
+      assert!(cache.call_api_x::<SomeAPI, _>(GetA).await);
+      assert_eq!(cache.call_api_x::<OtherAPI, _>(GetA).await, 1);
+    }
+    assert_eq!(cache.len(), 2);
+  }
+}
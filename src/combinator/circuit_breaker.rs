@@ -0,0 +1,361 @@
+//! Stop calling failing implementors for a while.
+
+use crate::{HasMethod, ImplsMethod};
+use core::time::Duration;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Circuit breaker for methods returning `Result`, e.g. of API clients.
+///
+/// While the circuit is [`CircuitState::Closed`] the outcomes of the last
+/// calls are recorded. When the failure rate reaches the threshold, the
+/// circuit becomes [`CircuitState::Open`] and all calls fail immediately with
+/// [`CircuitBreakerError::CircuitOpen`]. After the cool-down a few trial calls
+/// are let through in [`CircuitState::HalfOpen`] state, which closes the
+/// circuit if they succeed or opens it again otherwise.
+///
+/// Clones share the same state. Implements [`WithCircuitBreaker<API>`] for
+/// `B` implementing `API`.
+///
+/// ```ignore
+/// let config = CircuitBreakerConfig::new().failure_rate(0.2).cool_down(Duration::from_secs(5));
+/// let client = CircuitBreaker::new(config.per_method(true), client);
+/// client.call_api_x::<WithCircuitBreaker<WithErr<ClientError, SomeAPI>>, _>(GetA).await
+/// ```
+///
+/// **Implementor** combinator.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker<B> {
+  circuits: Arc<Circuits>,
+  inner: B,
+}
+
+impl<B> CircuitBreaker<B> {
+  pub fn new(config: CircuitBreakerConfig, inner: B) -> Self {
+    Self::with_circuits(Arc::new(Circuits::from(config)), inner)
+  }
+
+  pub(crate) fn with_circuits(circuits: Arc<Circuits>, inner: B) -> Self {
+    Self { circuits, inner }
+  }
+
+  /// Current state of the circuit of a method.
+  pub fn state(&self, method_name: &str) -> CircuitState {
+    self.circuits.state(method_name)
+  }
+
+  pub fn into_inner(self) -> B {
+    self.inner
+  }
+}
+
+/// Parameters of [`CircuitBreaker`].
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerConfig {
+  /// Share of failed calls which opens the circuit, default: `0.5`.
+  pub failure_rate: f64,
+  /// Number of last calls to compute the failure rate from, default: `20`.
+  pub window: usize,
+  /// Don't open the circuit until this many calls are recorded, default:
+  /// `10`.
+  pub min_calls: usize,
+  /// Time in open state before trial calls, default: 30s.
+  pub cool_down: Duration,
+  /// Number of successful trial calls to close the circuit, default: `1`.
+  pub half_open_calls: u32,
+  /// Separate circuit for each method, default: `false`.
+  pub per_method: bool,
+}
+
+impl Default for CircuitBreakerConfig {
+  fn default() -> Self {
+    Self {
+      failure_rate: 0.5,
+      window: 20,
+      min_calls: 10,
+      cool_down: Duration::from_secs(30),
+      half_open_calls: 1,
+      per_method: false,
+    }
+  }
+}
+
+impl CircuitBreakerConfig {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn failure_rate(self, failure_rate: f64) -> Self {
+    Self { failure_rate, ..self }
+  }
+
+  pub fn window(self, window: usize) -> Self {
+    Self { window, ..self }
+  }
+
+  pub fn min_calls(self, min_calls: usize) -> Self {
+    Self { min_calls, ..self }
+  }
+
+  pub fn cool_down(self, cool_down: Duration) -> Self {
+    Self { cool_down, ..self }
+  }
+
+  pub fn half_open_calls(self, half_open_calls: u32) -> Self {
+    Self { half_open_calls: half_open_calls.max(1), ..self }
+  }
+
+  pub fn per_method(self, per_method: bool) -> Self {
+    Self { per_method, ..self }
+  }
+}
+
+/// State of a [`CircuitBreaker`] circuit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+  /// Calls go through.
+  Closed,
+  /// Calls fail immediately.
+  Open,
+  /// A few trial calls go through.
+  HalfOpen,
+}
+
+#[derive(Debug)]
+pub(crate) struct Circuits {
+  config: CircuitBreakerConfig,
+  circuits: Mutex<HashMap<String, Circuit>>,
+}
+
+impl From<CircuitBreakerConfig> for Circuits {
+  fn from(config: CircuitBreakerConfig) -> Self {
+    Self { config, circuits: Mutex::default() }
+  }
+}
+
+#[derive(Debug, Default)]
+struct Circuit {
+  state: State,
+  /// Number of state changes, outcomes of calls started in an earlier
+  /// state are ignored.
+  generation: u64,
+  /// `true` for failures.
+  outcomes: VecDeque<bool>,
+}
+
+impl Circuit {
+  fn set_state(&mut self, state: State) {
+    self.state = state;
+    self.generation += 1;
+    self.outcomes.clear();
+  }
+}
+
+#[derive(Debug, Default)]
+enum State {
+  #[default]
+  Closed,
+  Open {
+    until: Instant,
+  },
+  HalfOpen {
+    trials: u32,
+    successes: u32,
+  },
+}
+
+impl Circuits {
+  fn with_circuit<T>(&self, method_name: &str, f: impl FnOnce(&mut Circuit) -> T) -> T {
+    let key = if self.config.per_method { method_name } else { "" };
+    let mut circuits = self.circuits.lock().unwrap_or_else(|err| err.into_inner());
+    match circuits.get_mut(key) {
+      Some(circuit) => f(circuit),
+      None => f(circuits.entry(key.to_owned()).or_default()),
+    }
+  }
+
+  fn state(&self, method_name: &str) -> CircuitState {
+    let now = Instant::now();
+    self.with_circuit(method_name, |circuit| match circuit.state {
+      State::Closed => CircuitState::Closed,
+      State::Open { until } if until > now => CircuitState::Open,
+      State::Open { .. } | State::HalfOpen { .. } => CircuitState::HalfOpen,
+    })
+  }
+
+  /// Check if a call may start, returns the generation of the circuit.
+  fn acquire(&self, method_name: &str) -> Option<u64> {
+    let now = Instant::now();
+    let half_open_calls = self.config.half_open_calls;
+    self.with_circuit(method_name, |circuit| {
+      match &mut circuit.state {
+        State::Closed => {}
+        State::Open { until } if *until > now => return None,
+        State::Open { .. } => circuit.set_state(State::HalfOpen { trials: 1, successes: 0 }),
+        State::HalfOpen { trials, .. } if *trials < half_open_calls => *trials += 1,
+        State::HalfOpen { .. } => return None,
+      }
+      Some(circuit.generation)
+    })
+  }
+
+  /// Record the outcome of a call started in `generation`, `None` if it was
+  /// cancelled.
+  fn record(&self, method_name: &str, generation: u64, failure: Option<bool>) {
+    let now = Instant::now();
+    let config = &self.config;
+    self.with_circuit(method_name, |circuit| {
+      if circuit.generation != generation {
+        return;
+      }
+      let open = State::Open { until: now + config.cool_down };
+      match (&mut circuit.state, failure) {
+        (State::Closed, Some(failure)) => {
+          circuit.outcomes.push_back(failure);
+          while circuit.outcomes.len() > config.window {
+            circuit.outcomes.pop_front();
+          }
+          let failures = circuit.outcomes.iter().filter(|failure| **failure).count();
+          let calls = circuit.outcomes.len();
+          if calls >= config.min_calls && failures as f64 >= config.failure_rate * calls as f64 {
+            circuit.set_state(open);
+          }
+        }
+        (State::HalfOpen { trials, .. }, None) => *trials = trials.saturating_sub(1),
+        (State::HalfOpen { .. }, Some(true)) => circuit.set_state(open),
+        (State::HalfOpen { successes, .. }, Some(false)) => {
+          *successes += 1;
+          if *successes >= config.half_open_calls {
+            circuit.set_state(State::Closed);
+          }
+        }
+        _ => {}
+      }
+    })
+  }
+}
+
+/// Records the call as cancelled unless its outcome is recorded.
+struct Call<'a> {
+  circuits: &'a Circuits,
+  method_name: &'static str,
+  generation: u64,
+  done: bool,
+}
+
+impl Call<'_> {
+  fn finish(mut self, failure: bool) {
+    self.done = true;
+    self.circuits.record(self.method_name, self.generation, Some(failure));
+  }
+}
+
+impl Drop for Call<'_> {
+  fn drop(&mut self) {
+    if !self.done {
+      self.circuits.record(self.method_name, self.generation, None);
+    }
+  }
+}
+
+widen_errors! {
+  /// Widens method errors with [`CircuitBreakerError`].
+  WithCircuitBreaker;
+  /// Error of [`WithCircuitBreaker`] methods.
+  CircuitBreakerError {
+    /// The circuit is open, the method wasn't called.
+    CircuitOpen,
+  }
+  fmt(f) {
+    CircuitBreakerError::CircuitOpen => write!(f, "Circuit is open"),
+  }
+}
+
+impl<API, M, B, R, Err> ImplsMethod<WithCircuitBreaker<API>, M> for CircuitBreaker<B>
+where
+  API: HasMethod<M, Res = Result<R, Err>>,
+  B: ImplsMethod<API, M> + Sync,
+  M: Send,
+{
+  async fn call_api(&self, req: M) -> Result<R, CircuitBreakerError<Err>> {
+    let Some(generation) = self.circuits.acquire(API::METHOD_NAME) else {
+      return Err(CircuitBreakerError::CircuitOpen);
+    };
+    let method_name = API::METHOD_NAME;
+    let call = Call { circuits: &self.circuits, method_name, generation, done: false };
+    let res = self.inner.call_api(req).await;
+    call.finish(res.is_err());
+    res.map_err(CircuitBreakerError::Err)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::CallApi;
+  use crate::func::FnImpl;
+  use crate::test::*;
+  use std::sync::atomic::{AtomicBool, Ordering};
+
+  #[tokio::test]
+  async fn circuit_breaker() {
+    let down = Arc::new(AtomicBool::new(true));
+    let backend = FnImpl::<SomeAPI>::builder()
+      .on(|GetA| async { true })
+      .on({
+        let down = down.clone();
+        move |PostA(_)| {
+          let down = down.load(Ordering::SeqCst);
+          async move { if down { Err("down".to_owned()) } else { Ok(()) } }
+        }
+      })
+      .build();
+    type Api = WithCircuitBreaker<SomeAPI>;
+
+    let config = CircuitBreakerConfig::new().min_calls(2).cool_down(Duration::from_millis(20));
+    let breaker = CircuitBreaker::new(config.per_method(true), backend);
+    let call = || breaker.call_api_x::<Api, _>(PostA(true));
+
+    assert_eq!(call().await, Err(CircuitBreakerError::Err("down".to_owned())));
+    assert_eq!(breaker.state("post_a"), CircuitState::Closed);
+    assert!(call().await.is_err());
+    assert_eq!(breaker.state("post_a"), CircuitState::Open);
+    assert_eq!(call().await, Err(CircuitBreakerError::CircuitOpen));
+    assert_eq!(breaker.state("get_a"), CircuitState::Closed);
+
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    assert_eq!(breaker.state("post_a"), CircuitState::HalfOpen);
+    assert!(matches!(call().await, Err(CircuitBreakerError::Err(_))));
+    assert_eq!(breaker.state("post_a"), CircuitState::Open);
+
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    down.store(false, Ordering::SeqCst);
+    call().await.unwrap();
+    assert_eq!(breaker.state("post_a"), CircuitState::Closed);
+  }
+
+  #[test]
+  fn stale_calls() {
+    let config = CircuitBreakerConfig::new().min_calls(1).cool_down(Duration::ZERO);
+    let circuits = Circuits::from(config.half_open_calls(2));
+    let state = || circuits.state("post_a");
+    let stale = circuits.acquire("post_a").unwrap();
+    let failed = circuits.acquire("post_a").unwrap();
+    circuits.record("post_a", failed, Some(true));
+    assert_eq!(state(), CircuitState::HalfOpen);
+
+    let trial = circuits.acquire("post_a").unwrap();
+    circuits.record("post_a", stale, None);
+    circuits.record("post_a", stale, None);
+    circuits.record("post_a", stale, Some(false));
+    circuits.record("post_a", trial, Some(false));
+    assert_eq!(state(), CircuitState::HalfOpen);
+    let cancelled = circuits.acquire("post_a").unwrap();
+    assert_eq!(circuits.acquire("post_a"), None);
+    circuits.record("post_a", cancelled, None);
+    let trial = circuits.acquire("post_a").unwrap();
+    circuits.record("post_a", trial, Some(false));
+    assert_eq!(state(), CircuitState::Closed);
+  }
+}
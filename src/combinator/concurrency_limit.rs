@@ -0,0 +1,211 @@
+//! Limit the number of concurrent calls.
+
+use crate::{HasMethod, ImplsMethod};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// Limits the number of concurrent calls of the whole API and of individual
+/// methods as configured in [`ConcurrencyLimitConfig`].
+///
+/// Clones share the same limits. Implements [`WithOverload<API>`] for `B`
+/// implementing `API`, [`OverloadError::Overloaded`] is returned only with
+/// [`LimitMode::Shed`].
+///
+/// ```ignore
+/// let config = ConcurrencyLimitConfig::new().limit(100).method_limit("post_a", 4);
+/// let backend = ConcurrencyLimit::new(config.mode(LimitMode::Shed), backend);
+/// backend.call_api_x::<WithOverload<SomeAPI>, _>(PostA(true)).await
+/// ```
+///
+/// **Implementor** combinator.
+#[derive(Debug, Clone)]
+pub struct ConcurrencyLimit<B> {
+  limits: Arc<Limits>,
+  inner: B,
+}
+
+impl<B> ConcurrencyLimit<B> {
+  pub fn new(config: ConcurrencyLimitConfig, inner: B) -> Self {
+    Self::with_limits(Arc::new(Limits::from(config)), inner)
+  }
+
+  pub(crate) fn with_limits(limits: Arc<Limits>, inner: B) -> Self {
+    Self { limits, inner }
+  }
+
+  /// Number of calls of a method that can start without waiting.
+  pub fn available_permits(&self, method_name: &str) -> usize {
+    let method = self.limits.methods.get(method_name).map(Semaphore::available_permits);
+    let api = self.limits.api.as_ref().map(Semaphore::available_permits);
+    method.into_iter().chain(api).min().unwrap_or(usize::MAX)
+  }
+
+  pub fn into_inner(self) -> B {
+    self.inner
+  }
+}
+
+/// Parameters of [`ConcurrencyLimit`]. Methods without a limit are not
+/// limited.
+#[derive(Debug, Clone, Default)]
+pub struct ConcurrencyLimitConfig {
+  /// Limit of concurrent calls of all of the methods together.
+  pub limit: Option<usize>,
+  /// Limits of concurrent calls by method name, in addition to
+  /// [`ConcurrencyLimitConfig::limit`].
+  pub method_limits: HashMap<String, usize>,
+  /// What to do when the limit is reached, default: [`LimitMode::Queue`].
+  pub mode: LimitMode,
+}
+
+impl ConcurrencyLimitConfig {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn limit(self, limit: usize) -> Self {
+    Self { limit: Some(limit), ..self }
+  }
+
+  pub fn method_limit(mut self, method_name: impl Into<String>, limit: usize) -> Self {
+    self.method_limits.insert(method_name.into(), limit);
+    self
+  }
+
+  pub fn mode(self, mode: LimitMode) -> Self {
+    Self { mode, ..self }
+  }
+}
+
+/// Behavior of [`ConcurrencyLimit`] when the limit is reached.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LimitMode {
+  /// Wait for a running call to finish.
+  #[default]
+  Queue,
+  /// Fail immediately with [`OverloadError::Overloaded`].
+  Shed,
+}
+
+#[derive(Debug)]
+pub(crate) struct Limits {
+  api: Option<Semaphore>,
+  methods: HashMap<String, Semaphore>,
+  mode: LimitMode,
+}
+
+impl From<ConcurrencyLimitConfig> for Limits {
+  fn from(config: ConcurrencyLimitConfig) -> Self {
+    Self {
+      api: config.limit.map(Semaphore::new),
+      methods: config.method_limits.into_iter().map(|(m, l)| (m, Semaphore::new(l))).collect(),
+      mode: config.mode,
+    }
+  }
+}
+
+impl Limits {
+  async fn acquire<'a>(
+    &self,
+    semaphore: Option<&'a Semaphore>,
+  ) -> Option<Option<SemaphorePermit<'a>>> {
+    let Some(semaphore) = semaphore else { return Some(None) };
+    let permit = match self.mode {
+      LimitMode::Queue => semaphore.acquire().await.ok(),
+      LimitMode::Shed => semaphore.try_acquire().ok(),
+    };
+    permit.map(Some)
+  }
+}
+
+widen_errors! {
+  /// Widens method errors with [`OverloadError`].
+  WithOverload;
+  /// Error of [`WithOverload`] methods.
+  OverloadError {
+    /// Concurrency limit is reached.
+    Overloaded,
+  }
+  fmt(f) {
+    OverloadError::Overloaded => write!(f, "Overloaded"),
+  }
+}
+
+impl<API, M, B, R, Err> ImplsMethod<WithOverload<API>, M> for ConcurrencyLimit<B>
+where
+  API: HasMethod<M, Res = Result<R, Err>>,
+  B: ImplsMethod<API, M> + Sync,
+  M: Send,
+{
+  async fn call_api(&self, req: M) -> Result<R, OverloadError<Err>> {
+    let limits = &self.limits;
+    let Some(_method_permit) = limits.acquire(limits.methods.get(API::METHOD_NAME)).await else {
+      return Err(OverloadError::Overloaded);
+    };
+    let Some(_api_permit) = limits.acquire(limits.api.as_ref()).await else {
+      return Err(OverloadError::Overloaded);
+    };
+    self.inner.call_api(req).await.map_err(OverloadError::Err)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::CallApi;
+  use crate::func::FnImpl;
+  use crate::test::*;
+  use tokio::sync::Notify;
+
+  #[tokio::test]
+  async fn concurrency_limit() {
+    let notify = Arc::new(Notify::new());
+    let backend = FnImpl::<SomeAPI>::builder()
+      .on(|GetA| async { true })
+      .on({
+        let notify = notify.clone();
+        move |PostA(_)| {
+          let notify = notify.clone();
+          async move {
+            notify.notified().await;
+            Ok(())
+          }
+        }
+      })
+      .build();
+    type Api = WithOverload<SomeAPI>;
+
+    let config = ConcurrencyLimitConfig::new().method_limit("post_a", 1);
+    let shed = ConcurrencyLimit::new(config.clone().mode(LimitMode::Shed), backend.clone());
+    let running = tokio::spawn({
+      let shed = shed.clone();
+      async move { shed.call_api_x::<Api, _>(PostA(true)).await }
+    });
+    while shed.available_permits("post_a") > 0 {
+      tokio::task::yield_now().await;
+    }
+    let res = shed.call_api_x::<Api, _>(PostA(true)).await;
+    assert_eq!(res, Err(OverloadError::Overloaded));
+    assert_eq!(shed.available_permits("get_a"), usize::MAX);
+    notify.notify_one();
+    running.await.unwrap().unwrap();
+
+    let queue = ConcurrencyLimit::new(config, backend);
+    let running = tokio::spawn({
+      let queue = queue.clone();
+      async move { queue.call_api_x::<Api, _>(PostA(true)).await }
+    });
+    while queue.available_permits("post_a") > 0 {
+      tokio::task::yield_now().await;
+    }
+    let queued = tokio::spawn({
+      let queue = queue.clone();
+      async move { queue.call_api_x::<Api, _>(PostA(true)).await }
+    });
+    notify.notify_one();
+    running.await.unwrap().unwrap();
+    notify.notify_one();
+    queued.await.unwrap().unwrap();
+  }
+}
@@ -1,6 +1,6 @@
 //! Map errors of APIs with [`Into`].
 
-use crate::{HasMethod, ImplsMethod, IsApi};
+use crate::{HasMethod, ImplsMethod, IsApi, MethodIndex};
 use core::marker::PhantomData;
 use documented::DocumentedOpt;
 
@@ -35,6 +35,10 @@ where
   const METHOD_DOCS: Option<&str> = API::METHOD_DOCS;
 }
 
+impl<API: MethodIndex<M>, M, ErrO> MethodIndex<M> for ErrInto<ErrO, API> {
+  type Index = API::Index;
+}
+
 impl<API, M, B, R, ErrO, ErrI> ImplsMethod<ErrInto<ErrO, API>, M> for ErrInto<ErrO, B>
 where
   ErrO: From<ErrI> + Send + Sync,
@@ -0,0 +1,184 @@
+//! Call a secondary implementor when the primary one fails.
+
+use super::{AllErrors, ErrorFilter, ErrorIf, Outcome};
+use crate::{HasMethod, ImplsMethod, IsApi};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Calls `primary` and, if it fails, `secondary` implementor of the same
+/// API, as configured with [`FallbackPolicy`]. Requests are cloned for the
+/// secondary call.
+///
+/// Implements every method of the API, so it can be served as a whole, e.g.
+/// with `mk_post_json_router`. Methods that aren't enabled in the policy and
+/// responses that are never errors according to [`Outcome`] are served by
+/// `primary`.
+///
+/// A remote client can fall back to a local implementor lifted with
+/// [`super::WithErr::new`]:
+///
+/// ```ignore
+/// let policy = FallbackPolicy::new()
+///   .method("get_a")
+///   .on_served(|method, branch| metrics.record(method, branch));
+/// let backend = Fallback::new(policy, client, WithErr::<ClientError, _>::new(local));
+/// ```
+///
+/// **Implementor** combinator.
+#[derive(Debug, Clone)]
+pub struct Fallback<P, S, F = AllErrors> {
+  policy: Arc<FallbackPolicy<F>>,
+  primary: P,
+  secondary: S,
+}
+
+impl<P, S, F> Fallback<P, S, F> {
+  pub fn new(policy: FallbackPolicy<F>, primary: P, secondary: S) -> Self {
+    Self::with_policy(Arc::new(policy), primary, secondary)
+  }
+
+  pub(crate) fn with_policy(policy: Arc<FallbackPolicy<F>>, primary: P, secondary: S) -> Self {
+    Self { policy, primary, secondary }
+  }
+
+  pub fn primary(&self) -> &P {
+    &self.primary
+  }
+
+  pub fn secondary(&self) -> &S {
+    &self.secondary
+  }
+}
+
+/// Implementor that served a call of [`Fallback`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Branch {
+  Primary,
+  Secondary,
+}
+
+type OnServed = Arc<dyn Fn(&'static str, Branch) + Send + Sync>;
+
+/// Parameters of [`Fallback`].
+#[derive(Clone, Default)]
+pub struct FallbackPolicy<F = AllErrors> {
+  /// Names of methods that fall back, default: none.
+  pub methods: HashSet<String>,
+  /// Errors that fall back, default: all.
+  pub fallback_if: F,
+  on_served: Option<OnServed>,
+}
+
+impl FallbackPolicy {
+  pub fn new() -> Self {
+    Self::default()
+  }
+}
+
+impl<F> FallbackPolicy<F> {
+  /// Enable fallback of a method by its name.
+  pub fn method(mut self, method_name: impl Into<String>) -> Self {
+    self.methods.insert(method_name.into());
+    self
+  }
+
+  /// Fall back only on errors matching `fallback_if`. It must take the error
+  /// type of each method returning `Result`, otherwise [`Fallback`] doesn't
+  /// implement the API.
+  pub fn fallback_if<Err, G: Fn(&Err) -> bool>(self, fallback_if: G) -> FallbackPolicy<ErrorIf<G>> {
+    let Self { methods, fallback_if: _, on_served } = self;
+    FallbackPolicy { methods, fallback_if: ErrorIf(fallback_if), on_served }
+  }
+
+  /// Report the implementor that served each call with the method name.
+  pub fn on_served(self, f: impl Fn(&'static str, Branch) + Send + Sync + 'static) -> Self {
+    Self { on_served: Some(Arc::new(f)), ..self }
+  }
+
+  fn served(&self, method_name: &'static str, branch: Branch) {
+    if let Some(on_served) = &self.on_served {
+      on_served(method_name, branch)
+    }
+  }
+}
+
+impl<F> core::fmt::Debug for FallbackPolicy<F> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_struct("FallbackPolicy").field("methods", &self.methods).finish_non_exhaustive()
+  }
+}
+
+impl<API, M, P, S, F> ImplsMethod<API, M> for Fallback<P, S, F>
+where
+  API: IsApi + HasMethod<M>,
+  API::Res: Outcome + Send,
+  F: ErrorFilter<API::Res> + Send + Sync,
+  P: ImplsMethod<API, M> + Sync,
+  S: ImplsMethod<API, M> + Sync,
+  M: Clone + Send + Sync,
+{
+  async fn call_api(&self, req: M) -> API::Res {
+    let policy = &self.policy;
+    if !policy.methods.contains(API::METHOD_NAME) {
+      policy.served(API::METHOD_NAME, Branch::Primary);
+      return self.primary.call_api(req).await;
+    }
+    let res = self.primary.call_api(req.clone()).await;
+    if !res.as_result().is_err_and(|err| policy.fallback_if.matches(err)) {
+      policy.served(API::METHOD_NAME, Branch::Primary);
+      return res;
+    }
+    #[cfg(feature = "tracing")]
+    tracing::warn!(
+      "rpc.service" = API::API_NAME,
+      "rpc.method" = API::METHOD_NAME,
+      "Primary failed, falling back"
+    );
+    policy.served(API::METHOD_NAME, Branch::Secondary);
+    self.secondary.call_api(req).await
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::func::FnImpl;
+  use crate::test::*;
+  use std::sync::Mutex;
+
+  #[tokio::test]
+  async fn fallback() {
+    let mk_backend = |err: Option<&'static str>| {
+      FnImpl::<SomeAPI>::builder()
+        .on(|GetA| async { true })
+        .on(move |PostA(_)| async move { err.map_or(Ok(()), |err| Err(err.to_owned())) })
+        .build()
+    };
+    let served = Arc::new(Mutex::new(Vec::new()));
+    let policy = FallbackPolicy::new().on_served({
+      let served = served.clone();
+      move |method, branch| served.lock().unwrap().push((method, branch))
+    });
+    let served = || std::mem::take(&mut *served.lock().unwrap());
+
+    let fallback = Fallback::new(policy.clone(), mk_backend(Some("down")), mk_backend(None));
+    assert_eq!(fallback.call_api(PostA(true)).await, Err("down".to_owned()));
+    assert_eq!(served(), [("post_a", Branch::Primary)]);
+
+    let policy = policy.method("post_a");
+    let fallback = Fallback::new(policy.clone(), mk_backend(Some("down")), mk_backend(None));
+    fallback.call_api(PostA(true)).await.unwrap();
+    assert_eq!(served(), [("post_a", Branch::Secondary)]);
+
+    let policy = policy.method("get_a").fallback_if(|err: &String| err != "bad request");
+    let fallback = Fallback::new(policy, mk_backend(Some("bad request")), mk_backend(None));
+    assert_eq!(fallback.call_api(PostA(true)).await, Err("bad request".to_owned()));
+    assert_eq!(served(), [("post_a", Branch::Primary)]);
+    assert!(fallback.call_api(GetA).await);
+    assert_eq!(served(), [("get_a", Branch::Primary)]);
+
+    #[cfg(feature = "post-json-axum")]
+    let _router: axum::Router = crate::server::post_json::mk_post_json_router::<SomeAPI, _>()
+      .with_state(Fallback::new(FallbackPolicy::new(), mk_backend(None), mk_backend(None)));
+  }
+}
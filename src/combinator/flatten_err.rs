@@ -0,0 +1,115 @@
+//! Flatten nested results of APIs, e.g. transport and application errors.
+
+use crate::{HasMethod, ImplsMethod, IsApi, MethodIndex};
+use core::marker::PhantomData;
+use documented::DocumentedOpt;
+
+/// Combinator to flatten `Result<Result<R, A>, T>` into `Result<R, E>` where
+/// `E: From<A> + From<T>`. Useful for clients, where `T` is a transport error
+/// and `A` is an application error:
+///
+/// ```ignore
+/// let client = FlattenErr::<MyError, _>::new(client);
+/// client.call_api(PostA(true)).await?;
+/// ```
+///
+/// Both **API** combinator and **implementor** combinator.
+#[repr(transparent)]
+pub struct FlattenErr<E, B>(pub B, PhantomData<E>);
+
+impl<E, B> FlattenErr<E, B> {
+  pub fn new(b: B) -> Self {
+    Self(b, PhantomData)
+  }
+}
+
+impl<E, B: Clone> Clone for FlattenErr<E, B> {
+  fn clone(&self) -> Self {
+    Self::new(self.0.clone())
+  }
+}
+
+impl<API: IsApi, E> IsApi for FlattenErr<E, API> {
+  type Methods = API::Methods;
+  const API_NAME: &str = API::API_NAME;
+  const API_VERSION: &str = API::API_VERSION;
+}
+
+impl<E, API: DocumentedOpt> DocumentedOpt for FlattenErr<E, API> {
+  const DOCS: Option<&str> = API::DOCS;
+}
+
+impl<M, R, A, T, E, API> HasMethod<M> for FlattenErr<E, API>
+where
+  API: HasMethod<M, Res = Result<Result<R, A>, T>>,
+{
+  type Res = Result<R, E>;
+  const METHOD_NAME: &str = API::METHOD_NAME;
+  const METHOD_DOCS: Option<&str> = API::METHOD_DOCS;
+}
+
+impl<API: MethodIndex<M>, M, E> MethodIndex<M> for FlattenErr<E, API> {
+  type Index = API::Index;
+}
+
+impl<API, M, B, R, A, T, E> ImplsMethod<FlattenErr<E, API>, M> for FlattenErr<E, B>
+where
+  E: From<A> + From<T> + Send + Sync,
+  B: ImplsMethod<API, M> + Send + Sync,
+  API: HasMethod<M, Res = Result<Result<R, A>, T>>,
+  M: Send,
+{
+  async fn call_api(&self, req: M) -> Result<R, E> {
+    Ok(self.0.call_api(req).await??)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::combinator::WithErr;
+
+  struct SomeApi;
+  crate::define_api! {SomeApi => {
+    "foo", Foo => Result<u8, AppErr>;
+  }}
+
+  struct Foo(Result<Result<u8, AppErr>, TransportErr>);
+  #[derive(Debug)]
+  struct AppErr;
+  #[derive(Debug)]
+  struct TransportErr;
+
+  #[derive(Debug, PartialEq)]
+  enum Error {
+    App,
+    Transport,
+  }
+
+  impl From<AppErr> for Error {
+    fn from(_: AppErr) -> Error {
+      Error::App
+    }
+  }
+
+  impl From<TransportErr> for Error {
+    fn from(_: TransportErr) -> Error {
+      Error::Transport
+    }
+  }
+
+  struct SomeClient;
+  impl ImplsMethod<WithErr<TransportErr, SomeApi>, Foo> for SomeClient {
+    async fn call_api(&self, Foo(res): Foo) -> Result<Result<u8, AppErr>, TransportErr> {
+      res
+    }
+  }
+
+  #[tokio::test]
+  async fn flatten() {
+    let client = FlattenErr::<Error, _>::new(SomeClient);
+    assert_eq!(client.call_api(Foo(Ok(Ok(1)))).await, Ok(1));
+    assert_eq!(client.call_api(Foo(Ok(Err(AppErr)))).await, Err(Error::App));
+    assert_eq!(client.call_api(Foo(Err(TransportErr))).await, Err(Error::Transport));
+  }
+}
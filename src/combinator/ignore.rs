@@ -1,6 +1,6 @@
 //! Erasing API level errors.
 
-use crate::{HasMethod, ImplsMethod, IsApi};
+use crate::{HasMethod, ImplsMethod, IsApi, MethodIndex};
 use documented::DocumentedOpt;
 
 /// Transforming return types of all methods that must be `Result<R, E>` to
@@ -26,6 +26,10 @@ where
   const METHOD_DOCS: Option<&str> = API::METHOD_DOCS;
 }
 
+impl<API: MethodIndex<M>, M> MethodIndex<M> for IgnoreOk<API> {
+  type Index = API::Index;
+}
+
 impl<API: DocumentedOpt> DocumentedOpt for IgnoreOk<API> {
   const DOCS: Option<&str> = API::DOCS;
 }
@@ -62,6 +66,10 @@ impl<API: IsApi + HasMethod<M>, M> HasMethod<M> for IgnoreRes<API> {
   const METHOD_DOCS: Option<&str> = API::METHOD_DOCS;
 }
 
+impl<API: MethodIndex<M>, M> MethodIndex<M> for IgnoreRes<API> {
+  type Index = API::Index;
+}
+
 impl<API: DocumentedOpt> DocumentedOpt for IgnoreRes<API> {
   const DOCS: Option<&str> = API::DOCS;
 }
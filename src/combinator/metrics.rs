@@ -0,0 +1,345 @@
+//! Call counts, errors and latencies per method.
+
+use super::Outcome;
+use crate::{HasMethod, ImplsMethod, IsApi};
+use core::fmt::Write;
+use core::time::Duration;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Records calls, errors, calls in flight and latencies of each method into
+/// a [`MetricsSink`], e.g. [`MetricsRegistry`].
+///
+/// Errors are determined by [`Outcome`] of the responses.
+///
+/// ```ignore
+/// let registry = MetricsRegistry::new();
+/// let router = mk_post_json_router::<SomeAPI, _>()
+///   .with_state(Metrics::new(registry.clone(), backend))
+///   .route("/metrics", registry.axum_route());
+/// ```
+///
+/// **Implementor** combinator.
+#[derive(Clone)]
+pub struct Metrics<B> {
+  sink: Arc<dyn MetricsSink>,
+  inner: B,
+}
+
+impl<B> Metrics<B> {
+  pub fn new(sink: impl MetricsSink + 'static, inner: B) -> Self {
+    Self::with_sink(Arc::new(sink), inner)
+  }
+
+  pub(crate) fn with_sink(sink: Arc<dyn MetricsSink>, inner: B) -> Self {
+    Self { sink, inner }
+  }
+
+  pub fn into_inner(self) -> B {
+    self.inner
+  }
+}
+
+impl<B: core::fmt::Debug> core::fmt::Debug for Metrics<B> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_struct("Metrics").field("inner", &self.inner).finish_non_exhaustive()
+  }
+}
+
+/// Receiver of [`Metrics`] events.
+pub trait MetricsSink: Send + Sync {
+  fn call_started(&self, api_name: &'static str, method_name: &'static str);
+
+  /// `error` is `None` if the call was cancelled.
+  fn call_finished(
+    &self,
+    api_name: &'static str,
+    method_name: &'static str,
+    latency: Duration,
+    error: Option<bool>,
+  );
+}
+
+impl<API, M, B> ImplsMethod<API, M> for Metrics<B>
+where
+  API: IsApi + HasMethod<M>,
+  API::Res: Outcome,
+  B: ImplsMethod<API, M> + Sync,
+  M: Send,
+{
+  async fn call_api(&self, req: M) -> API::Res {
+    self.sink.call_started(API::API_NAME, API::METHOD_NAME);
+    let mut call = Call {
+      sink: self.sink.as_ref(),
+      names: (API::API_NAME, API::METHOD_NAME),
+      started: Instant::now(),
+      error: None,
+    };
+    let res = self.inner.call_api(req).await;
+    call.error = Some(res.is_error());
+    res
+  }
+}
+
+/// Reports the end of a call when dropped.
+struct Call<'a> {
+  sink: &'a dyn MetricsSink,
+  names: (&'static str, &'static str),
+  started: Instant,
+  error: Option<bool>,
+}
+
+impl Drop for Call<'_> {
+  fn drop(&mut self) {
+    let (api_name, method_name) = self.names;
+    self.sink.call_finished(api_name, method_name, self.started.elapsed(), self.error);
+  }
+}
+
+/// Default latency histogram buckets in seconds, same as of Prometheus
+/// clients.
+pub const DEFAULT_BUCKETS: &[f64] =
+  &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// [`MetricsSink`] that keeps the metrics in memory and renders them in
+/// Prometheus text format. Clones share the same metrics.
+#[derive(Debug, Clone)]
+pub struct MetricsRegistry {
+  inner: Arc<Registry>,
+}
+
+#[derive(Debug)]
+struct Registry {
+  namespace: String,
+  buckets: Vec<f64>,
+  methods: Mutex<BTreeMap<(&'static str, &'static str), MethodMetrics>>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct MethodMetrics {
+  calls: u64,
+  errors: u64,
+  cancelled: u64,
+  in_flight: i64,
+  /// Non-cumulative counts of each bucket.
+  buckets: Vec<u64>,
+  latency_sum: f64,
+}
+
+impl Default for MetricsRegistry {
+  fn default() -> Self {
+    Self::with_config("aisil", DEFAULT_BUCKETS.to_vec())
+  }
+}
+
+impl MetricsRegistry {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Registry with custom metric names prefix and latency buckets in
+  /// seconds.
+  pub fn with_config(namespace: impl Into<String>, mut buckets: Vec<f64>) -> Self {
+    buckets.sort_by(f64::total_cmp);
+    let registry = Registry { namespace: namespace.into(), buckets, methods: Mutex::default() };
+    Self { inner: Arc::new(registry) }
+  }
+
+  fn update(
+    &self,
+    api_name: &'static str,
+    method_name: &'static str,
+    f: impl FnOnce(&mut MethodMetrics),
+  ) {
+    let mut methods = self.inner.methods.lock().unwrap_or_else(|err| err.into_inner());
+    f(methods.entry((api_name, method_name)).or_default())
+  }
+
+  /// Render all of the metrics in Prometheus text exposition format.
+  pub fn render(&self) -> String {
+    let Registry { namespace: ns, buckets, methods } = self.inner.as_ref();
+    let methods = methods.lock().unwrap_or_else(|err| err.into_inner()).clone();
+    let labels = |(api, method): &(&str, &str)| {
+      format!("api=\"{}\",method=\"{}\"", escape(api), escape(method))
+    };
+    let mut out = String::new();
+    let mut metric =
+      |name: &str, kind: &str, help: &str, value: &dyn Fn(&MethodMetrics) -> String| {
+        let _ = writeln!(out, "# HELP {ns}_{name} {help}\n# TYPE {ns}_{name} {kind}");
+        for (names, metrics) in &methods {
+          let _ = writeln!(out, "{ns}_{name}{{{}}} {}", labels(names), value(metrics));
+        }
+      };
+    metric("calls_total", "counter", "Finished calls.", &|m| m.calls.to_string());
+    metric("errors_total", "counter", "Calls with error responses.", &|m| m.errors.to_string());
+    metric("cancelled_total", "counter", "Cancelled calls.", &|m| m.cancelled.to_string());
+    metric("in_flight", "gauge", "Calls in progress.", &|m| m.in_flight.to_string());
+
+    let name = format!("{ns}_call_duration_seconds");
+    let _ = writeln!(out, "# HELP {name} Latency of finished calls.\n# TYPE {name} histogram");
+    for (names, metrics) in &methods {
+      let labels = labels(names);
+      let mut cumulative = 0;
+      for (le, count) in buckets.iter().zip(&metrics.buckets) {
+        cumulative += count;
+        let _ = writeln!(out, "{name}_bucket{{{labels},le=\"{le}\"}} {cumulative}");
+      }
+      let _ = writeln!(out, "{name}_bucket{{{labels},le=\"+Inf\"}} {}", metrics.calls);
+      let _ = writeln!(out, "{name}_sum{{{labels}}} {}", metrics.latency_sum);
+      let _ = writeln!(out, "{name}_count{{{labels}}} {}", metrics.calls);
+    }
+    out
+  }
+
+  /// `GET` route serving [`MetricsRegistry::render`].
+  #[cfg(feature = "post-json-axum")]
+  pub fn axum_route<S: Clone + Send + Sync + 'static>(&self) -> axum::routing::MethodRouter<S> {
+    let registry = self.clone();
+    axum::routing::get(async move || {
+      let content_type = "text/plain; version=0.0.4; charset=utf-8";
+      ([(axum::http::header::CONTENT_TYPE, content_type)], registry.render())
+    })
+  }
+}
+
+fn escape(label: &str) -> String {
+  label.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+impl MetricsSink for MetricsRegistry {
+  fn call_started(&self, api_name: &'static str, method_name: &'static str) {
+    self.update(api_name, method_name, |m| m.in_flight += 1);
+  }
+
+  fn call_finished(
+    &self,
+    api_name: &'static str,
+    method_name: &'static str,
+    latency: Duration,
+    error: Option<bool>,
+  ) {
+    let buckets = &self.inner.buckets;
+    self.update(api_name, method_name, |m| {
+      m.in_flight -= 1;
+      let Some(error) = error else {
+        m.cancelled += 1;
+        return;
+      };
+      m.calls += 1;
+      m.errors += u64::from(error);
+      let latency = latency.as_secs_f64();
+      m.latency_sum += latency;
+      m.buckets.resize(buckets.len(), 0);
+      if let Some(bucket) = buckets.iter().position(|le| latency <= *le) {
+        m.buckets[bucket] += 1;
+      }
+    });
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::CallApi;
+  use crate::test::*;
+
+  #[tokio::test]
+  async fn metrics() {
+    let registry = MetricsRegistry::with_config("test", vec![10.0, 0.0]);
+    let backend = Metrics::new(registry.clone(), SomeBackend::default());
+    backend.call_api_x::<SomeAPI, _>(PostA(true)).await.unwrap();
+    assert!(backend.call_api_x::<SomeAPI, _>(PostA(true)).await.is_err());
+    assert!(backend.call_api_x::<SomeAPI, _>(GetA).await);
+
+    let rendered = registry.render();
+    let lines = [
+      "# TYPE test_calls_total counter",
+      r#"test_calls_total{api="SomeAPI",method="get_a"} 1"#,
+      r#"test_calls_total{api="SomeAPI",method="post_a"} 2"#,
+      r#"test_errors_total{api="SomeAPI",method="get_a"} 0"#,
+      r#"test_errors_total{api="SomeAPI",method="post_a"} 1"#,
+      r#"test_in_flight{api="SomeAPI",method="post_a"} 0"#,
+      "# TYPE test_call_duration_seconds histogram",
+      r#"test_call_duration_seconds_bucket{api="SomeAPI",method="post_a",le="0"} 0"#,
+      r#"test_call_duration_seconds_bucket{api="SomeAPI",method="post_a",le="10"} 2"#,
+      r#"test_call_duration_seconds_bucket{api="SomeAPI",method="post_a",le="+Inf"} 2"#,
+      r#"test_call_duration_seconds_count{api="SomeAPI",method="post_a"} 2"#,
+    ];
+    for line in lines {
+      assert!(rendered.lines().any(|l| l == line), "missing {line:?} in:\n{rendered}");
+    }
+  }
+
+  #[tokio::test]
+  async fn custom_responses() {
+    use crate::combinator::PlainResponse;
+    use crate::func::FnImpl;
+
+    struct Profile;
+    impl PlainResponse for Profile {}
+
+    enum Login {
+      Session,
+      Failure,
+    }
+    impl Outcome for Login {
+      type Ok = ();
+      type Err = ();
+      fn as_result(&self) -> Result<&(), &()> {
+        match self {
+          Login::Session => Ok(&()),
+          Login::Failure => Err(&()),
+        }
+      }
+    }
+
+    struct UserAPI;
+    crate::define_api! { UserAPI => {
+      "profile", GetA => Profile;
+      "login", PostA => Login;
+    } }
+
+    let backend = FnImpl::<UserAPI>::builder()
+      .on(|GetA| async { Profile })
+      .on(|PostA(ok)| async move { if ok { Login::Session } else { Login::Failure } })
+      .build();
+    let registry = MetricsRegistry::with_config("test", vec![]);
+    let backend = Metrics::new(registry.clone(), backend);
+    backend.call_api_x::<UserAPI, _>(GetA).await;
+    backend.call_api_x::<UserAPI, _>(PostA(false)).await;
+
+    let rendered = registry.render();
+    let lines = [
+      r#"test_errors_total{api="UserAPI",method="profile"} 0"#,
+      r#"test_errors_total{api="UserAPI",method="login"} 1"#,
+    ];
+    for line in lines {
+      assert!(rendered.lines().any(|l| l == line), "missing {line:?} in:\n{rendered}");
+    }
+  }
+
+  #[cfg(all(feature = "post-json-axum", feature = "client"))]
+  #[tokio::test]
+  async fn axum_route() {
+    use crate::server::post_json::mk_post_json_router;
+    use std::net::Ipv4Addr;
+
+    let registry = MetricsRegistry::new();
+    let router = mk_post_json_router::<SomeAPI, _>()
+      .route("/metrics", registry.axum_route())
+      .with_state(Metrics::new(registry.clone(), SomeBackend::default()));
+    let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server_thread = tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+    let http = reqwest::Client::new();
+    http.post(format!("http://{addr}/get_a")).json(&GetA).send().await.unwrap();
+    let res = http.get(format!("http://{addr}/metrics")).send().await.unwrap();
+    assert!(res.headers()["content-type"].to_str().unwrap().starts_with("text/plain"));
+    let body = res.text().await.unwrap();
+    assert!(body.contains(r#"aisil_calls_total{api="SomeAPI",method="get_a"} 1"#), "{body}");
+
+    server_thread.abort();
+  }
+}
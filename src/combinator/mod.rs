@@ -1,24 +1,78 @@
 //! A few built-in API and implementor combinators.
 
+#[macro_use]
+mod widen;
+
 #[doc(hidden)]
 pub mod compose;
 #[doc(hidden)]
 pub use compose::*;
 
+mod builder;
+pub use builder::*;
+
+mod outcome;
+pub use outcome::*;
+
 mod ignore;
 pub use ignore::*;
 
 mod err_into;
 pub use err_into::*;
 
+mod flatten_err;
+pub use flatten_err::*;
+
 mod with_err;
 pub use with_err::*;
 
+#[cfg(feature = "cache")]
+mod cache;
+#[cfg(feature = "cache")]
+pub use cache::*;
+
+#[cfg(all(feature = "cache", feature = "tokio"))]
+mod single_flight;
+#[cfg(all(feature = "cache", feature = "tokio"))]
+pub use single_flight::*;
+
+mod circuit_breaker;
+pub use circuit_breaker::*;
+
+mod metrics;
+pub use metrics::*;
+
+mod fallback;
+pub use fallback::*;
+
 #[cfg(feature = "tokio")]
 mod fork_and_forget;
 #[cfg(feature = "tokio")]
 pub use fork_and_forget::*;
 
+#[cfg(feature = "tokio")]
+mod concurrency_limit;
+#[cfg(feature = "tokio")]
+pub use concurrency_limit::*;
+
+#[cfg(feature = "tokio")]
+mod rate_limit;
+#[cfg(feature = "tokio")]
+pub use rate_limit::*;
+
+#[cfg(feature = "tokio")]
+mod retry;
+#[cfg(feature = "tokio")]
+pub use retry::*;
+
+#[cfg(feature = "tokio")]
+mod timeout;
+#[cfg(feature = "tokio")]
+pub use timeout::*;
+
+#[cfg(feature = "tower")]
+pub mod tower;
+
 #[cfg(feature = "tracing")]
 pub mod tracing;
 //#[cfg(feature = "tracing")]
@@ -0,0 +1,104 @@
+//! Success or error of method responses.
+
+use core::convert::Infallible;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/// Success or error of a method response, so that combinators like
+/// [`super::Metrics`] and [`super::tracing::ApiTracer`] handle `Result` and
+/// plain responses alike.
+///
+/// Implemented for `Result` and for [`PlainResponse`] types, which are never
+/// errors. Other responses opt in with one of them:
+///
+/// ```ignore
+/// impl PlainResponse for Profile {}
+///
+/// impl Outcome for LoginResponse {
+///   type Ok = Session;
+///   type Err = LoginFailure;
+///   fn as_result(&self) -> Result<&Session, &LoginFailure> {
+///     match self {
+///       LoginResponse::Session(session) => Ok(session),
+///       LoginResponse::Failure(failure) => Err(failure),
+///     }
+///   }
+/// }
+/// ```
+pub trait Outcome {
+  type Ok: ?Sized;
+  type Err;
+
+  fn as_result(&self) -> Result<&Self::Ok, &Self::Err>;
+
+  fn is_error(&self) -> bool {
+    self.as_result().is_err()
+  }
+}
+
+impl<T, E> Outcome for Result<T, E> {
+  type Ok = T;
+  type Err = E;
+
+  fn as_result(&self) -> Result<&T, &E> {
+    self.as_ref()
+  }
+}
+
+/// Response that is never an error.
+pub trait PlainResponse {}
+
+impl<T: PlainResponse> Outcome for T {
+  type Ok = T;
+  type Err = Infallible;
+
+  fn as_result(&self) -> Result<&T, &Infallible> {
+    Ok(self)
+  }
+}
+
+macro_rules! impl_plain_response {
+  ($($t:ty),*) => { $(impl PlainResponse for $t {})* };
+}
+
+impl_plain_response!((), bool, char, String, &'static str);
+impl_plain_response!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+impl<T> PlainResponse for Option<T> {}
+impl<T> PlainResponse for Vec<T> {}
+impl<T> PlainResponse for Box<T> {}
+impl<T> PlainResponse for BTreeSet<T> {}
+impl<T, S> PlainResponse for HashSet<T, S> {}
+impl<K, V> PlainResponse for BTreeMap<K, V> {}
+impl<K, V, S> PlainResponse for HashMap<K, V, S> {}
+
+/// Predicate on [`Outcome::Err`] of responses `Res`, e.g. errors to retry
+/// with [`super::RetryPolicy::retry_if`].
+pub trait ErrorFilter<Res: Outcome> {
+  fn matches(&self, err: &Res::Err) -> bool;
+}
+
+/// Matches all errors.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AllErrors;
+
+impl<Res: Outcome> ErrorFilter<Res> for AllErrors {
+  fn matches(&self, _err: &Res::Err) -> bool {
+    true
+  }
+}
+
+/// Matches errors of `Result` responses with `F`, so `F` taking an error of
+/// a different type fails to compile. Plain responses have no errors.
+#[derive(Debug, Clone, Copy)]
+pub struct ErrorIf<F>(pub F);
+
+impl<T, E, F: Fn(&E) -> bool> ErrorFilter<Result<T, E>> for ErrorIf<F> {
+  fn matches(&self, err: &E) -> bool {
+    (self.0)(err)
+  }
+}
+
+impl<R: PlainResponse, F> ErrorFilter<R> for ErrorIf<F> {
+  fn matches(&self, err: &Infallible) -> bool {
+    match *err {}
+  }
+}
@@ -0,0 +1,342 @@
+//! Token bucket rate limiting.
+
+use crate::{HasMethod, ImplsMethod};
+use core::time::Duration;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Limits the rate of calls with token buckets configured in
+/// [`RateLimitConfig`]: one for the whole API, one per method and one per
+/// key extracted by [`RateLimitKey`].
+///
+/// Clones share the same buckets. Implements [`WithRateLimit<API>`] for `B`
+/// implementing `API`.
+///
+/// `post_json` servers of [`WithRateLimit`] APIs respond with `429 Too Many
+/// Requests` and `Retry-After` header when the response is
+/// [`RateLimitError::RateLimited`], so rejections recovered by outer
+/// combinators, e.g. [`crate::combinator::Retry`], are answered with `200`.
+/// Rejections nested in method errors, e.g. of a downstream service, are not.
+///
+/// ```ignore
+/// let config = RateLimitConfig::new()
+///   .method_limit("post_a", Quota::per_second(10))
+///   .key_limit(Quota::per_minute(60));
+/// let state = RateLimit::new(config, backend);
+/// let router = mk_post_json_router::<WithRateLimit<SomeAPI>, _>()
+///   .layer(axum::middleware::from_fn(async |req: Request, next: Next| {
+///     let key = client_ip(&req);
+///     with_rate_limit_key(key, next.run(req)).await
+///   }))
+///   .with_state(state);
+/// ```
+///
+/// **Implementor** combinator.
+#[derive(Debug, Clone)]
+pub struct RateLimit<B, K = ContextKey> {
+  buckets: Arc<Buckets>,
+  key: K,
+  inner: B,
+}
+
+impl<B> RateLimit<B> {
+  /// Rate limit with keys set by [`with_rate_limit_key`].
+  pub fn new(config: RateLimitConfig, inner: B) -> Self {
+    Self::with_key(config, ContextKey, inner)
+  }
+}
+
+impl<B, K> RateLimit<B, K> {
+  pub fn with_key(config: RateLimitConfig, key: K, inner: B) -> Self {
+    Self::with_buckets(Arc::new(Buckets::from(config)), key, inner)
+  }
+
+  pub(crate) fn with_buckets(buckets: Arc<Buckets>, key: K, inner: B) -> Self {
+    Self { buckets, key, inner }
+  }
+
+  pub fn into_inner(self) -> B {
+    self.inner
+  }
+}
+
+/// Parameters of [`RateLimit`]. Calls are not limited by default.
+#[derive(Debug, Clone, Default)]
+pub struct RateLimitConfig {
+  /// Quota of all of the methods together.
+  pub limit: Option<Quota>,
+  /// Quotas by method name.
+  pub method_limits: HashMap<String, Quota>,
+  /// Quota of each key, for all of the methods together.
+  pub key_limit: Option<Quota>,
+}
+
+impl RateLimitConfig {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn limit(self, quota: Quota) -> Self {
+    Self { limit: Some(quota), ..self }
+  }
+
+  pub fn method_limit(mut self, method_name: impl Into<String>, quota: Quota) -> Self {
+    self.method_limits.insert(method_name.into(), quota);
+    self
+  }
+
+  pub fn key_limit(self, quota: Quota) -> Self {
+    Self { key_limit: Some(quota), ..self }
+  }
+}
+
+/// Token bucket parameters, `burst` calls at once and then one call per
+/// `period`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+  pub burst: u32,
+  pub period: Duration,
+}
+
+impl Quota {
+  pub fn per_second(calls: u32) -> Self {
+    Self::per(Duration::from_secs(1), calls)
+  }
+
+  pub fn per_minute(calls: u32) -> Self {
+    Self::per(Duration::from_secs(60), calls)
+  }
+
+  /// `calls` per `duration` with the burst of `calls`.
+  pub fn per(duration: Duration, calls: u32) -> Self {
+    let calls = calls.max(1);
+    Self { burst: calls, period: duration / calls }
+  }
+
+  pub fn burst(self, burst: u32) -> Self {
+    Self { burst: burst.max(1), ..self }
+  }
+}
+
+/// Extracts the key of per-key buckets from a request, calls without a key
+/// are not limited per key.
+pub trait RateLimitKey<M> {
+  fn key(&self, req: &M) -> Option<String>;
+}
+
+/// Key set with [`with_rate_limit_key`], e.g. by a server middleware.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ContextKey;
+
+impl<M> RateLimitKey<M> for ContextKey {
+  fn key(&self, _req: &M) -> Option<String> {
+    RATE_LIMIT_KEY.try_with(Clone::clone).ok()
+  }
+}
+
+impl<M, F: Fn(&M) -> Option<String>> RateLimitKey<M> for F {
+  fn key(&self, req: &M) -> Option<String> {
+    self(req)
+  }
+}
+
+tokio::task_local! {
+  static RATE_LIMIT_KEY: String;
+}
+
+/// Run `fut` with the key used by [`ContextKey`].
+pub async fn with_rate_limit_key<F: Future>(key: impl Into<String>, fut: F) -> F::Output {
+  RATE_LIMIT_KEY.scope(key.into(), fut).await
+}
+
+#[derive(Debug)]
+pub(crate) struct Buckets {
+  config: RateLimitConfig,
+  state: Mutex<BucketsState>,
+}
+
+#[derive(Debug, Default)]
+struct BucketsState {
+  api: Option<Bucket>,
+  methods: HashMap<String, Bucket>,
+  keys: HashMap<String, Bucket>,
+  /// Last time keys with full buckets were dropped.
+  pruned: Option<Instant>,
+}
+
+impl From<RateLimitConfig> for Buckets {
+  fn from(config: RateLimitConfig) -> Self {
+    Self { config, state: Mutex::default() }
+  }
+}
+
+impl Buckets {
+  /// Take a token from each of the buckets or return the time until all of
+  /// them have one.
+  fn take(&self, method_name: &str, key: Option<String>) -> Result<(), Duration> {
+    let now = Instant::now();
+    let config = &self.config;
+    let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+    let BucketsState { api, methods, keys, pruned } = &mut *state;
+    // Buckets of keys idle for the time of a full refill are full, so keys
+    // are kept for at most twice that time after their last call.
+    if let Some(quota) = &config.key_limit
+      && pruned.is_none_or(|pruned| now.duration_since(pruned) >= quota.period * quota.burst)
+    {
+      keys.retain(|_, bucket| !bucket.refill(quota, now).is_full(quota));
+      *pruned = Some(now);
+    }
+    let buckets = [
+      config
+        .limit
+        .as_ref()
+        .map(|quota| (quota, api.get_or_insert_with(|| Bucket::new(quota, now)))),
+      config.method_limits.get_key_value(method_name).map(|(name, quota)| {
+        (quota, methods.entry(name.clone()).or_insert_with(|| Bucket::new(quota, now)))
+      }),
+      config
+        .key_limit
+        .as_ref()
+        .zip(key)
+        .map(|(quota, key)| (quota, keys.entry(key).or_insert_with(|| Bucket::new(quota, now)))),
+    ];
+    let mut buckets: Vec<_> = buckets.into_iter().flatten().collect();
+    let retry_after =
+      buckets.iter_mut().map(|(quota, bucket)| bucket.refill(quota, now).wait(quota));
+    match retry_after.max() {
+      Some(retry_after) if !retry_after.is_zero() => Err(retry_after),
+      _ => {
+        buckets.into_iter().for_each(|(_, bucket)| bucket.tokens -= 1.0);
+        Ok(())
+      }
+    }
+  }
+}
+
+#[derive(Debug)]
+struct Bucket {
+  tokens: f64,
+  updated: Instant,
+}
+
+impl Bucket {
+  fn new(quota: &Quota, now: Instant) -> Self {
+    Self { tokens: quota.burst as f64, updated: now }
+  }
+
+  fn refill(&mut self, quota: &Quota, now: Instant) -> &mut Self {
+    let refilled = now.duration_since(self.updated).as_secs_f64() / quota.period.as_secs_f64();
+    self.tokens = (self.tokens + refilled).min(quota.burst as f64);
+    self.updated = now;
+    self
+  }
+
+  fn is_full(&self, quota: &Quota) -> bool {
+    self.tokens >= quota.burst as f64
+  }
+
+  /// Time until the next token.
+  fn wait(&self, quota: &Quota) -> Duration {
+    quota.period.mul_f64((1.0 - self.tokens).max(0.0))
+  }
+}
+
+widen_errors! {
+  /// Widens method errors with [`RateLimitError`].
+  WithRateLimit;
+  /// Error of [`WithRateLimit`] methods.
+  RateLimitError {
+    /// Quota is exhausted, next call may succeed after `retry_after`.
+    RateLimited { retry_after: Duration },
+  }
+  fmt(f) {
+    RateLimitError::RateLimited { retry_after } => {
+      write!(f, "Rate limited, retry after {retry_after:?}")
+    }
+  }
+  retry_after(res) {
+    match res {
+      Err(RateLimitError::RateLimited { retry_after }) => Some(*retry_after),
+      _ => None,
+    }
+  }
+}
+
+impl<API, M, B, K, R, Err> ImplsMethod<WithRateLimit<API>, M> for RateLimit<B, K>
+where
+  API: HasMethod<M, Res = Result<R, Err>>,
+  B: ImplsMethod<API, M> + Sync,
+  K: RateLimitKey<M> + Sync,
+  M: Send,
+{
+  async fn call_api(&self, req: M) -> Result<R, RateLimitError<Err>> {
+    let key = self.key.key(&req);
+    if let Err(retry_after) = self.buckets.take(API::METHOD_NAME, key) {
+      return Err(RateLimitError::RateLimited { retry_after });
+    }
+    self.inner.call_api(req).await.map_err(RateLimitError::Err)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::CallApi;
+  use crate::func::FnImpl;
+  use crate::test::*;
+
+  #[tokio::test]
+  async fn rate_limit() {
+    let backend = FnImpl::<SomeAPI>::builder()
+      .on(|GetA| async { true })
+      .on(|PostA(_)| async { Ok(()) })
+      .build();
+    type Api = WithRateLimit<SomeAPI>;
+    let limited = |res| matches!(res, Err(RateLimitError::RateLimited { .. }));
+
+    let config = RateLimitConfig::new().method_limit("post_a", Quota::per_minute(2));
+    let backend = RateLimit::new(config.key_limit(Quota::per_minute(1)), backend);
+    with_rate_limit_key("a", backend.call_api_x::<Api, _>(PostA(true))).await.unwrap();
+    let res = with_rate_limit_key("a", backend.call_api_x::<Api, _>(PostA(true))).await;
+    assert!(limited(res));
+    with_rate_limit_key("b", backend.clone().call_api_x::<Api, _>(PostA(true))).await.unwrap();
+    let res = backend.call_api_x::<Api, _>(PostA(true)).await;
+    let Err(RateLimitError::RateLimited { retry_after }) = res else { panic!() };
+    assert!(retry_after > Duration::from_secs(25) && retry_after <= Duration::from_secs(30));
+
+    let config = RateLimitConfig::new().key_limit(Quota::per_minute(1));
+    let by_flag = |PostA(a): &PostA| Some(a.to_string());
+    let backend = RateLimit::with_key(config, by_flag, backend.into_inner());
+    backend.call_api_x::<Api, _>(PostA(true)).await.unwrap();
+    backend.call_api_x::<Api, _>(PostA(false)).await.unwrap();
+    assert!(limited(backend.call_api_x::<Api, _>(PostA(true)).await));
+  }
+
+  #[test]
+  fn prune_idle_keys() {
+    let config = RateLimitConfig::new().key_limit(Quota::per(Duration::from_millis(20), 2));
+    let buckets = Buckets::from(config);
+    let keys = || buckets.state.lock().unwrap().keys.len();
+    buckets.take("post_a", Some("a".to_owned())).unwrap();
+    buckets.take("post_a", Some("b".to_owned())).unwrap();
+    assert_eq!(keys(), 2);
+    std::thread::sleep(Duration::from_millis(30));
+    buckets.take("post_a", Some("c".to_owned())).unwrap();
+    assert_eq!(keys(), 1);
+  }
+
+  #[test]
+  fn retry_after() {
+    use crate::HasMethod;
+    use crate::combinator::WithErr;
+
+    let limited = RateLimitError::RateLimited { retry_after: Duration::from_secs(1) };
+    let retry_after = <WithRateLimit<SomeAPI> as HasMethod<PostA>>::retry_after;
+    assert_eq!(retry_after(&Err(limited.clone())), Some(Duration::from_secs(1)));
+    assert_eq!(retry_after(&Err(RateLimitError::Err("a".to_owned()))), None);
+    assert_eq!(retry_after(&Ok(())), None);
+    let nested = <WithErr<(), WithRateLimit<SomeAPI>> as HasMethod<PostA>>::retry_after;
+    assert_eq!(nested(&Ok(Err(limited))), None);
+  }
+}
@@ -0,0 +1,228 @@
+//! Retry failed calls with exponential backoff.
+
+use super::{AllErrors, ErrorFilter, ErrorIf, Outcome};
+use crate::{HasMethod, ImplsMethod, IsApi};
+use core::time::Duration;
+use std::collections::HashSet;
+
+/// Retries errors of methods according to a [`RetryPolicy`], responses are
+/// split into successes and errors by [`Outcome`].
+///
+/// Only methods marked with [`RetryPolicy::idempotent`] or whitelisted with
+/// [`RetryPolicy::method`] are retried, others are called once, as are
+/// methods with plain responses. Requests are cloned for each attempt.
+///
+/// ```ignore
+/// let policy = RetryPolicy::new()
+///   .max_attempts(5)
+///   .idempotent::<SomeAPI, GetA>()
+///   .retry_if(|err: &ClientError| matches!(err, ClientError::Timeout));
+/// let client = Retry(policy, client);
+/// ```
+///
+/// **Implementor** combinator.
+#[derive(Debug, Clone)]
+pub struct Retry<P, B>(pub P, pub B);
+
+/// Parameters of [`Retry`].
+#[derive(Clone)]
+pub struct RetryPolicy<F = AllErrors> {
+  /// Maximum number of calls including the first one, default: `3`.
+  pub max_attempts: u32,
+  /// Delays between attempts.
+  pub backoff: Backoff,
+  /// Names of methods to retry, default: none.
+  pub methods: HashSet<&'static str>,
+  /// Errors to retry, default: all.
+  pub retry_if: F,
+}
+
+impl RetryPolicy {
+  pub fn new() -> Self {
+    Self::default()
+  }
+}
+
+impl Default for RetryPolicy {
+  fn default() -> Self {
+    Self {
+      max_attempts: 3,
+      backoff: Backoff::default(),
+      methods: HashSet::new(),
+      retry_if: AllErrors,
+    }
+  }
+}
+
+impl<F> RetryPolicy<F> {
+  pub fn max_attempts(self, max_attempts: u32) -> Self {
+    Self { max_attempts, ..self }
+  }
+
+  pub fn backoff(self, backoff: Backoff) -> Self {
+    Self { backoff, ..self }
+  }
+
+  /// Mark method `M` of `API` as idempotent, so it's safe to retry.
+  pub fn idempotent<API: HasMethod<M>, M>(self) -> Self {
+    self.method(API::METHOD_NAME)
+  }
+
+  /// Whitelist a method by its name.
+  pub fn method(mut self, method_name: &'static str) -> Self {
+    self.methods.insert(method_name);
+    self
+  }
+
+  /// Retry only errors matching `retry_if`. It must take the error type of
+  /// each method returning `Result`, otherwise [`Retry`] doesn't implement
+  /// the API.
+  pub fn retry_if<Err, G: Fn(&Err) -> bool>(self, retry_if: G) -> RetryPolicy<ErrorIf<G>> {
+    let Self { max_attempts, backoff, methods, retry_if: _ } = self;
+    RetryPolicy { max_attempts, backoff, methods, retry_if: ErrorIf(retry_if) }
+  }
+}
+
+impl<F> core::fmt::Debug for RetryPolicy<F> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_struct("RetryPolicy")
+      .field("max_attempts", &self.max_attempts)
+      .field("backoff", &self.backoff)
+      .field("methods", &self.methods)
+      .finish_non_exhaustive()
+  }
+}
+
+/// Exponential backoff with jitter.
+#[derive(Debug, Clone, Copy)]
+pub struct Backoff {
+  /// Delay after the first attempt, default: 100ms.
+  pub initial: Duration,
+  /// Upper bound of the delay, default: 10s.
+  pub max: Duration,
+  /// Factor of the delay growth, default: `2`.
+  pub multiplier: u32,
+  /// Randomize each delay within `[delay / 2, delay]`, default: `true`.
+  pub jitter: bool,
+}
+
+impl Default for Backoff {
+  fn default() -> Self {
+    Self {
+      initial: Duration::from_millis(100),
+      max: Duration::from_secs(10),
+      multiplier: 2,
+      jitter: true,
+    }
+  }
+}
+
+impl Backoff {
+  /// Delay after failed `attempt`, counting from `1`.
+  pub fn delay(&self, attempt: u32) -> Duration {
+    let factor = self.multiplier.saturating_pow(attempt.saturating_sub(1));
+    let delay = self.initial.saturating_mul(factor).min(self.max);
+    if !self.jitter {
+      return delay;
+    }
+    delay.mul_f64(0.5 + 0.5 * fastrand::f64())
+  }
+}
+
+impl<API, M, B, F> ImplsMethod<API, M> for Retry<RetryPolicy<F>, B>
+where
+  API: IsApi + HasMethod<M>,
+  API::Res: Outcome + Send,
+  F: ErrorFilter<API::Res> + Sync,
+  B: ImplsMethod<API, M> + Sync,
+  M: Clone + Send + Sync,
+{
+  async fn call_api(&self, req: M) -> API::Res {
+    let Retry(policy, inner) = self;
+    if !policy.methods.contains(API::METHOD_NAME) {
+      return inner.call_api(req).await;
+    }
+    let mut attempt = 1;
+    loop {
+      let res = inner.call_api(req.clone()).await;
+      let retry = attempt < policy.max_attempts;
+      if !(retry && res.as_result().is_err_and(|err| policy.retry_if.matches(err))) {
+        return res;
+      }
+      let delay = policy.backoff.delay(attempt);
+      #[cfg(feature = "tracing")]
+      tracing::warn!(
+        "rpc.service" = API::API_NAME,
+        "rpc.method" = API::METHOD_NAME,
+        attempt,
+        ?delay,
+        "Attempt failed, retrying"
+      );
+      tokio::time::sleep(delay).await;
+      attempt += 1;
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::func::FnImpl;
+  use crate::test::*;
+  use std::sync::Arc;
+  use std::sync::atomic::{AtomicU32, Ordering};
+
+  #[tokio::test]
+  async fn retry() {
+    let calls = Arc::new(AtomicU32::new(0));
+    let backend = FnImpl::<SomeAPI>::builder()
+      .on({
+        let calls = calls.clone();
+        move |GetA| {
+          calls.fetch_add(1, Ordering::SeqCst);
+          async { true }
+        }
+      })
+      .on({
+        let calls = calls.clone();
+        move |PostA(_)| {
+          let n = calls.fetch_add(1, Ordering::SeqCst);
+          async move { if n % 3 == 2 { Ok(()) } else { Err(format!("attempt {n}")) } }
+        }
+      })
+      .build();
+
+    let backoff = Backoff { initial: Duration::from_millis(1), ..Default::default() };
+    let policy = RetryPolicy::new().backoff(backoff);
+
+    let retry = Retry(policy.clone(), backend.clone());
+    assert!(retry.call_api(PostA(true)).await.is_err());
+    assert_eq!(calls.swap(0, Ordering::SeqCst), 1);
+
+    let retry = Retry(policy.clone().idempotent::<SomeAPI, PostA>(), backend.clone());
+    retry.call_api(PostA(true)).await.unwrap();
+    assert_eq!(calls.swap(0, Ordering::SeqCst), 3);
+
+    let retry = Retry(policy.clone().method("post_a").max_attempts(2), backend.clone());
+    assert!(retry.call_api(PostA(true)).await.is_err());
+    assert_eq!(calls.swap(0, Ordering::SeqCst), 2);
+
+    let policy =
+      policy.method("post_a").method("get_a").retry_if(|err: &String| err != "attempt 0");
+    let retry = Retry(policy, backend);
+    assert!(retry.call_api(PostA(true)).await.is_err());
+    assert_eq!(calls.swap(0, Ordering::SeqCst), 1);
+    assert!(retry.call_api(GetA).await);
+    assert_eq!(calls.swap(0, Ordering::SeqCst), 1);
+  }
+
+  #[test]
+  fn backoff() {
+    let backoff = Backoff { jitter: false, ..Default::default() };
+    assert_eq!(backoff.delay(1), Duration::from_millis(100));
+    assert_eq!(backoff.delay(3), Duration::from_millis(400));
+    assert_eq!(backoff.delay(100), Duration::from_secs(10));
+    let jittered = Backoff::default().delay(2);
+    assert!(jittered >= Duration::from_millis(100) && jittered <= Duration::from_millis(200));
+  }
+}
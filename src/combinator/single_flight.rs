@@ -0,0 +1,188 @@
+//! Coalesce concurrent calls with equal requests.
+
+use super::cache::{Key, key};
+use crate::{HasMethod, ImplsMethod, IsApi};
+use core::any::Any;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+
+/// Lets only one of concurrent calls with equal requests reach `B`, the
+/// others wait for it and receive a clone of its response. Requests are
+/// compared like keys of [`super::Cache`].
+///
+/// Only the given methods are coalesced, these should be free of side
+/// effects. If the call that reached `B` is cancelled, one of the waiters
+/// calls `B` instead. Clones share the calls in flight.
+///
+/// ```ignore
+/// let backend = SingleFlight::new(["get_a"], Cache::new(config, backend));
+/// ```
+///
+/// **Implementor** combinator.
+#[derive(Debug, Clone)]
+pub struct SingleFlight<B> {
+  flights: Arc<Flights>,
+  inner: B,
+}
+
+impl<B> SingleFlight<B> {
+  pub fn new<S: Into<String>>(methods: impl IntoIterator<Item = S>, inner: B) -> Self {
+    Self::with_flights(Arc::new(Flights::new(methods)), inner)
+  }
+
+  pub(crate) fn with_flights(flights: Arc<Flights>, inner: B) -> Self {
+    Self { flights, inner }
+  }
+
+  pub fn into_inner(self) -> B {
+    self.inner
+  }
+}
+
+/// `Arc<OnceCell<Res>>` of the response type in the key.
+type Flight = Box<dyn Any + Send + Sync>;
+
+#[derive(Debug, Default)]
+pub(crate) struct Flights {
+  methods: HashSet<String>,
+  in_flight: Mutex<HashMap<Key, Flight>>,
+}
+
+impl Flights {
+  pub(crate) fn new<S: Into<String>>(methods: impl IntoIterator<Item = S>) -> Self {
+    let methods = methods.into_iter().map(Into::into).collect();
+    Self { methods, in_flight: Mutex::default() }
+  }
+
+  fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<Key, Flight>> {
+    self.in_flight.lock().unwrap_or_else(|err| err.into_inner())
+  }
+}
+
+impl<API, M, B> ImplsMethod<API, M> for SingleFlight<B>
+where
+  API: IsApi + HasMethod<M>,
+  API::Res: Clone + Send + Sync + 'static,
+  B: ImplsMethod<API, M> + Sync,
+  M: Serialize + Send + 'static,
+{
+  async fn call_api(&self, req: M) -> API::Res {
+    let flights = &self.flights;
+    let key = match key::<API, M>(&req) {
+      Some(key) if flights.methods.contains(API::METHOD_NAME) => key,
+      _ => return self.inner.call_api(req).await,
+    };
+    let flight = flights
+      .lock()
+      .entry(key.clone())
+      .or_insert_with(|| Box::new(Arc::new(OnceCell::<API::Res>::new())))
+      .downcast_ref::<Arc<OnceCell<API::Res>>>()
+      .cloned();
+    let Some(flight) = flight else {
+      return self.inner.call_api(req).await;
+    };
+    let res = flight.get_or_init(|| self.inner.call_api(req)).await.clone();
+    let mut in_flight = flights.lock();
+    let current = in_flight.get(&key).and_then(|current| current.downcast_ref());
+    if current.is_some_and(|current| Arc::ptr_eq(current, &flight)) {
+      in_flight.remove(&key);
+    }
+    res
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::func::FnImpl;
+  use crate::test::*;
+  use core::time::Duration;
+  use std::sync::atomic::{AtomicU32, Ordering};
+
+  #[tokio::test]
+  async fn single_flight() {
+    let calls = Arc::new(AtomicU32::new(0));
+    let backend = FnImpl::<SomeAPI>::builder()
+      .on({
+        let calls = calls.clone();
+        move |GetA| {
+          calls.fetch_add(1, Ordering::SeqCst);
+          async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            true
+          }
+        }
+      })
+      .on({
+        let calls = calls.clone();
+        move |PostA(_)| {
+          calls.fetch_add(1, Ordering::SeqCst);
+          async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Ok(())
+          }
+        }
+      })
+      .build();
+    let calls = || calls.swap(0, Ordering::SeqCst);
+    let backend = SingleFlight::new(["get_a"], backend);
+
+    let res = tokio::join!(backend.call_api(GetA), backend.call_api(GetA), backend.call_api(GetA));
+    assert_eq!((res, calls()), ((true, true, true), 1));
+    assert!(backend.flights.lock().is_empty());
+
+    let (a, b) = tokio::join!(backend.call_api(PostA(true)), backend.call_api(PostA(true)));
+    assert_eq!((a, b, calls()), (Ok(()), Ok(()), 2));
+
+    let cancelled = tokio::time::timeout(Duration::from_millis(5), backend.call_api(GetA));
+    let (cancelled, res) = tokio::join!(cancelled, backend.call_api(GetA));
+    assert!(cancelled.is_err() && res);
+    assert_eq!(calls(), 2);
+  }
+
+  #[tokio::test]
+  async fn equal_json_of_different_requests() {
+    use crate::CallApi;
+
+    struct OtherAPI;
+    crate::define_api! { OtherAPI => {
+      "get_a", GetB => u32;
+    } }
+
+    struct ThirdAPI;
+    crate::define_api! { ThirdAPI => {
+      "get_a", GetA => u32;
+    } }
+
+    struct Backend;
+    impl ImplsMethod<SomeAPI, GetA> for Backend {
+      async fn call_api(&self, _: GetA) -> bool {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        true
+      }
+    }
+    impl ImplsMethod<OtherAPI, GetB> for Backend {
+      async fn call_api(&self, _: GetB) -> u32 {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        1
+      }
+    }
+
+    impl ImplsMethod<ThirdAPI, GetA> for Backend {
+      async fn call_api(&self, _: GetA) -> u32 {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        3
+      }
+    }
+
+    let backend = SingleFlight::new(["get_a"], Backend);
+    let res = tokio::join!(
+      backend.call_api_x::<SomeAPI, _>(GetA),
+      backend.call_api_x::<OtherAPI, _>(GetB),
+      backend.call_api_x::<ThirdAPI, _>(GetA)
+    );
+    assert_eq!(res, (true, 1, 3));
+  }
+}
@@ -0,0 +1,114 @@
+//! Bound the duration of calls.
+
+use crate::{HasMethod, ImplsMethod};
+use core::time::Duration;
+use std::collections::HashMap;
+
+/// Fails calls that take longer than configured in [`TimeoutConfig`] with
+/// [`TimeoutError::TimedOut`].
+///
+/// Implements [`WithTimeout<API>`] for `B` implementing `API`.
+///
+/// ```ignore
+/// let config = TimeoutConfig::new().timeout(Duration::from_secs(5));
+/// let backend = Timeout(config.method_timeout("post_a", Duration::from_secs(30)), backend);
+/// backend.call_api_x::<WithTimeout<SomeAPI>, _>(PostA(true)).await
+/// ```
+///
+/// **Implementor** combinator.
+#[derive(Debug, Clone)]
+pub struct Timeout<B>(pub TimeoutConfig, pub B);
+
+/// Parameters of [`Timeout`]. Methods without a timeout are not bounded.
+#[derive(Debug, Clone, Default)]
+pub struct TimeoutConfig {
+  /// Timeout of all of the methods.
+  pub timeout: Option<Duration>,
+  /// Overrides of [`TimeoutConfig::timeout`] by method name.
+  pub method_timeouts: HashMap<String, Duration>,
+}
+
+impl TimeoutConfig {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn timeout(self, timeout: Duration) -> Self {
+    Self { timeout: Some(timeout), ..self }
+  }
+
+  pub fn method_timeout(mut self, method_name: impl Into<String>, timeout: Duration) -> Self {
+    self.method_timeouts.insert(method_name.into(), timeout);
+    self
+  }
+
+  /// Timeout of a method with overrides applied.
+  pub fn timeout_of(&self, method_name: &str) -> Option<Duration> {
+    self.method_timeouts.get(method_name).copied().or(self.timeout)
+  }
+}
+
+widen_errors! {
+  /// Widens method errors with [`TimeoutError`].
+  WithTimeout;
+  /// Error of [`WithTimeout`] methods.
+  TimeoutError {
+    /// Call didn't finish within the given duration.
+    TimedOut(Duration),
+  }
+  fmt(f) {
+    TimeoutError::TimedOut(timeout) => write!(f, "Timed out after {timeout:?}"),
+  }
+}
+
+impl<API, M, B, R, Err> ImplsMethod<WithTimeout<API>, M> for Timeout<B>
+where
+  API: HasMethod<M, Res = Result<R, Err>>,
+  B: ImplsMethod<API, M> + Sync,
+  M: Send,
+{
+  async fn call_api(&self, req: M) -> Result<R, TimeoutError<Err>> {
+    let Timeout(config, inner) = self;
+    let Some(timeout) = config.timeout_of(API::METHOD_NAME) else {
+      return inner.call_api(req).await.map_err(TimeoutError::Err);
+    };
+    match tokio::time::timeout(timeout, inner.call_api(req)).await {
+      Ok(res) => res.map_err(TimeoutError::Err),
+      Err(_) => Err(TimeoutError::TimedOut(timeout)),
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::CallApi;
+  use crate::func::FnImpl;
+  use crate::test::*;
+
+  #[tokio::test]
+  async fn timeout() {
+    let backend = FnImpl::<SomeAPI>::builder()
+      .on(|GetA| async { true })
+      .on(|PostA(slow)| async move {
+        if slow {
+          tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+        Err("posted".to_owned())
+      })
+      .build();
+
+    let config = TimeoutConfig::new().method_timeout("post_a", Duration::from_millis(20));
+    let backend = Timeout(config, backend);
+    type Api = WithTimeout<SomeAPI>;
+
+    let res = backend.call_api_x::<Api, _>(PostA(false)).await;
+    assert_eq!(res, Err(TimeoutError::Err("posted".to_owned())));
+    let res = backend.call_api_x::<Api, _>(PostA(true)).await;
+    assert_eq!(res, Err(TimeoutError::TimedOut(Duration::from_millis(20))));
+    assert_eq!(backend.0.timeout_of("get_a"), None);
+    let config = backend.0.clone().timeout(Duration::from_secs(1));
+    assert_eq!(config.timeout_of("get_a"), Some(Duration::from_secs(1)));
+    assert_eq!(config.timeout_of("post_a"), Some(Duration::from_millis(20)));
+  }
+}
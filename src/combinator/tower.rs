@@ -0,0 +1,172 @@
+//! Interoperability with `tower` services and layers.
+//!
+//! - [`TowerService`] exposes an implementor as a `tower::Service`.
+//! - [`FromTower`] makes an implementor out of a `tower::Service`.
+//! - [`ApiLayer`] wraps a whole implementor with a `tower::Layer`.
+
+use crate::combinator::WithErr;
+use crate::{HasMethod, ImplsMethod, IsApi};
+use ::tower::{Layer, Service, ServiceExt};
+use core::convert::Infallible;
+use core::marker::PhantomData;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use std::sync::Arc;
+
+/// `tower::Service<M, Response = API::Res>` for every method `M` of an
+/// implementor. Never fails and is always ready.
+pub struct TowerService<API, E> {
+  implementor: Arc<E>,
+  api_marker: PhantomData<fn() -> API>,
+}
+
+impl<API, E> TowerService<API, E> {
+  pub fn new(implementor: E) -> Self {
+    Self { implementor: Arc::new(implementor), api_marker: PhantomData }
+  }
+}
+
+impl<API, E> Clone for TowerService<API, E> {
+  fn clone(&self) -> Self {
+    Self { implementor: self.implementor.clone(), api_marker: PhantomData }
+  }
+}
+
+impl<API, E, M> Service<M> for TowerService<API, E>
+where
+  API: HasMethod<M> + 'static,
+  API::Res: 'static,
+  E: ImplsMethod<API, M> + Send + Sync + 'static,
+  M: Send + 'static,
+{
+  type Response = API::Res;
+  type Error = Infallible;
+  type Future = Pin<Box<dyn Future<Output = Result<API::Res, Infallible>> + Send>>;
+
+  fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+    Poll::Ready(Ok(()))
+  }
+
+  fn call(&mut self, req: M) -> Self::Future {
+    let implementor = self.implementor.clone();
+    Box::pin(async move { Ok(implementor.call_api(req).await) })
+  }
+}
+
+/// Implementor of `WithErr<S::Error, API>` out of a `tower::Service` which
+/// implements `Service<M, Response = API::Res>` for methods `M`.
+///
+/// The service is cloned for each call, so state shared between calls must be
+/// shared between clones, as it is for most of tower middleware.
+///
+/// **Implementor** combinator.
+pub struct FromTower<API, S> {
+  service: S,
+  api_marker: PhantomData<fn() -> API>,
+}
+
+impl<API, S> FromTower<API, S> {
+  pub fn new(service: S) -> Self {
+    Self { service, api_marker: PhantomData }
+  }
+
+  pub fn into_inner(self) -> S {
+    self.service
+  }
+}
+
+impl<API, S: Clone> Clone for FromTower<API, S> {
+  fn clone(&self) -> Self {
+    Self::new(self.service.clone())
+  }
+}
+
+impl<API, S, M> ImplsMethod<WithErr<S::Error, API>, M> for FromTower<API, S>
+where
+  API: IsApi + HasMethod<M>,
+  S: Service<M, Response = API::Res> + Clone + Send + Sync,
+  S::Future: Send,
+  M: Send,
+{
+  async fn call_api(&self, req: M) -> Result<API::Res, S::Error> {
+    self.service.clone().oneshot(req).await
+  }
+}
+
+/// Wraps a whole implementor with a `tower::Layer`, e.g. timeouts, load
+/// shedding or buffers from the tower ecosystem. The layer must produce a
+/// service implementing `Service<M>` for each method `M`, which is the case
+/// for most generic middleware.
+///
+/// ```ignore
+/// let backend = ApiLayer(ConcurrencyLimitLayer::new(8)).layer::<SomeAPI, _>(SomeBackend::default());
+/// backend.call_api(GetA).await?;
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct ApiLayer<L>(pub L);
+
+impl<L> ApiLayer<L> {
+  /// Wrap `implementor` of `API`, the result implements
+  /// `WithErr<L::Service::Error, API>`.
+  pub fn layer<API, E>(&self, implementor: E) -> FromTower<API, L::Service>
+  where
+    L: Layer<TowerService<API, E>>,
+  {
+    FromTower::new(self.0.layer(TowerService::new(implementor)))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::test::*;
+  use std::sync::atomic::{AtomicUsize, Ordering};
+
+  #[tokio::test]
+  async fn tower_service() {
+    let mut svc = TowerService::<SomeAPI, _>::new(SomeBackend::default());
+    let ready = ServiceExt::<PostA>::ready(&mut svc).await.unwrap();
+    assert_eq!(ready.call(PostA(true)).await.unwrap(), Ok(()));
+    assert!(svc.oneshot(GetA).await.unwrap());
+  }
+
+  #[tokio::test]
+  async fn from_tower() {
+    let svc = ::tower::service_fn(async |PostA(a)| match a {
+      true => Ok(Ok(())),
+      false => Err("nope"),
+    });
+    let backend = FromTower::<SomeAPI, _>::new(svc);
+    assert_eq!(backend.call_api(PostA(true)).await, Ok(Ok(())));
+    assert_eq!(backend.call_api(PostA(false)).await, Err("nope"));
+  }
+
+  /// Counts calls of all methods.
+  #[derive(Clone)]
+  struct Counting<S>(S, Arc<AtomicUsize>);
+
+  impl<S: Service<M>, M> Service<M> for Counting<S> {
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+      self.0.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: M) -> S::Future {
+      self.1.fetch_add(1, Ordering::SeqCst);
+      self.0.call(req)
+    }
+  }
+
+  #[tokio::test]
+  async fn api_layer() {
+    let count = Arc::new(AtomicUsize::new(0));
+    let layer = ::tower::layer::layer_fn(|svc| Counting(svc, count.clone()));
+    let backend = ApiLayer(layer).layer::<SomeAPI, _>(SomeBackend::default());
+    backend.call_api(PostA(true)).await.unwrap().unwrap();
+    assert!(backend.call_api(GetA).await.unwrap());
+    assert_eq!(count.load(Ordering::SeqCst), 2);
+  }
+}
@@ -1,49 +1,340 @@
-//! Simple tracing combinator.
+//! Tracing combinator.
 
+use super::Outcome;
 use crate::{HasMethod, ImplsMethod, IsApi};
-use core::fmt::Debug;
+use core::fmt::{self, Debug, Display};
+use core::marker::PhantomData;
+use std::time::Instant;
+use tracing::{Instrument, Level, field::Empty};
 
-/// Simple tracing combinator.
+/// Tracing combinator.
 ///
-/// Adds a span and can optionally trace requests, responses and errors.
+/// Adds a span with OpenTelemetry style fields `rpc.system`, `rpc.service`,
+/// `rpc.method`, `otel.status_code` and `latency_ms`, and can optionally
+/// trace requests, responses and errors. Responses are split into successes
+/// and errors by [`Outcome`]. Requests and responses are formatted with `F`,
+/// errors with `G`, both `Debug` by default, see [`ApiTracerConfig`].
 ///
 /// **Implementor** combinator.
 #[derive(Debug, Clone, Copy)]
-pub struct ApiTracer<E>(pub ApiTracerConfig, pub E);
+pub struct ApiTracer<E, F = DebugFormat, G = DebugFormat>(pub ApiTracerConfig<F, G>, pub E);
 
-/// Parameters to [`ApiTracer`]. By default only logs errors.
-#[derive(Debug, Clone, Copy)]
-pub struct ApiTracerConfig {
-  /// Enable `debug!("Request: {req:?}")`, default: `false`.
-  pub request: bool, // TODO: tracing::level_filters::LevelFilter or similar
-  /// Enable `debug!("Response: {res:?}")`, default: `false`.
-  pub response: bool,
-  /// Enable `error!("{err:?}")`, default: `true`.
-  pub error: bool,
+/// Parameters to [`ApiTracer`], `None` level disables the event. By default
+/// only logs errors.
+///
+/// ```ignore
+/// let config = ApiTracerConfig::new().request(Level::DEBUG).redact().display_errors();
+/// ```
+#[derive(Debug)]
+pub struct ApiTracerConfig<F = DebugFormat, G = DebugFormat> {
+  /// Level of `Request: {req}` events, default: `None`.
+  pub request: Option<Level>,
+  /// Level of `Response: {res}` events, default: `None`.
+  pub response: Option<Level>,
+  /// Level of `{err}` events, default: `Some(Level::ERROR)`.
+  pub error: Option<Level>,
+  /// Format `F` of requests and responses and `G` of errors.
+  pub format: PhantomData<fn() -> (F, G)>,
+}
+
+impl<F, G> Clone for ApiTracerConfig<F, G> {
+  fn clone(&self) -> Self {
+    *self
+  }
 }
 
+impl<F, G> Copy for ApiTracerConfig<F, G> {}
+
 impl Default for ApiTracerConfig {
   fn default() -> Self {
-    Self { request: false, response: false, error: true }
+    Self { request: None, response: None, error: Some(Level::ERROR), format: PhantomData }
+  }
+}
+
+impl ApiTracerConfig {
+  pub fn new() -> Self {
+    Self::default()
+  }
+}
+
+impl<F, G> ApiTracerConfig<F, G> {
+  pub fn request(self, level: impl Into<Option<Level>>) -> Self {
+    Self { request: level.into(), ..self }
+  }
+
+  pub fn response(self, level: impl Into<Option<Level>>) -> Self {
+    Self { response: level.into(), ..self }
+  }
+
+  pub fn error(self, level: impl Into<Option<Level>>) -> Self {
+    Self { error: level.into(), ..self }
+  }
+
+  /// Format requests and responses with `F2`.
+  pub fn format<F2>(self) -> ApiTracerConfig<F2, G> {
+    let Self { request, response, error, .. } = self;
+    ApiTracerConfig { request, response, error, format: PhantomData }
+  }
+
+  /// Format errors with `G2`.
+  pub fn error_format<G2>(self) -> ApiTracerConfig<F, G2> {
+    let Self { request, response, error, .. } = self;
+    ApiTracerConfig { request, response, error, format: PhantomData }
+  }
+
+  /// Format requests and responses with [`Redact`].
+  pub fn redact(self) -> ApiTracerConfig<RedactFormat, G> {
+    self.format()
+  }
+
+  /// Format errors with `Display`.
+  pub fn display_errors(self) -> ApiTracerConfig<F, DisplayFormat> {
+    self.error_format()
+  }
+}
+
+/// Format of values traced by [`ApiTracer`].
+pub trait TraceFormat<T: ?Sized> {
+  fn fmt(value: &T, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+}
+
+/// Format with `Debug`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DebugFormat;
+
+impl<T: Debug + ?Sized> TraceFormat<T> for DebugFormat {
+  fn fmt(value: &T, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    Debug::fmt(value, f)
   }
 }
 
-impl<API, E, Req, Res, Err> ImplsMethod<API, Req> for ApiTracer<E>
+/// Format with `Display`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DisplayFormat;
+
+impl<T: Display + ?Sized> TraceFormat<T> for DisplayFormat {
+  fn fmt(value: &T, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    Display::fmt(value, f)
+  }
+}
+
+/// Format with [`Redact`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RedactFormat;
+
+impl<T: Redact + ?Sized> TraceFormat<T> for RedactFormat {
+  fn fmt(value: &T, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    value.redact(f)
+  }
+}
+
+/// Format of traced requests and responses, so that sensitive data can be
+/// masked. Defaults to `Debug`, used with [`ApiTracerConfig::redact`].
+///
+/// ```ignore
+/// impl Redact for GetA {}
+///
+/// impl Redact for Login {
+///   fn redact(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///     f.debug_struct("Login").field("user", &self.user).field("password", &"***").finish()
+///   }
+/// }
+/// ```
+pub trait Redact: Debug {
+  fn redact(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    Debug::fmt(self, f)
+  }
+}
+
+macro_rules! impl_redact {
+  ($($t:ty),*) => { $(impl Redact for $t {})* };
+}
+
+impl_redact!((), bool, char, str, String);
+impl_redact!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+impl<T: Redact> Redact for Option<T> {
+  fn redact(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Some(value) => {
+        f.debug_tuple("Some").field(&Formatted::<RedactFormat, _>::new(value)).finish()
+      }
+      None => f.write_str("None"),
+    }
+  }
+}
+
+impl<T: Redact> Redact for Vec<T> {
+  fn redact(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_list().entries(self.iter().map(Formatted::<RedactFormat, _>::new)).finish()
+  }
+}
+
+/// Formats with [`TraceFormat`] `F`.
+struct Formatted<'a, F, T: ?Sized>(&'a T, PhantomData<F>);
+
+impl<'a, F, T: ?Sized> Formatted<'a, F, T> {
+  fn new(value: &'a T) -> Self {
+    Formatted(value, PhantomData)
+  }
+}
+
+impl<F: TraceFormat<T>, T: ?Sized> Debug for Formatted<'_, F, T> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    F::fmt(self.0, f)
+  }
+}
+
+impl<F: TraceFormat<T>, T: ?Sized> Display for Formatted<'_, F, T> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    F::fmt(self.0, f)
+  }
+}
+
+/// Event with a level known only at runtime.
+macro_rules! event {
+  ($level:expr, $($arg:tt)+) => {
+    match $level {
+      Level::ERROR => tracing::error!($($arg)+),
+      Level::WARN => tracing::warn!($($arg)+),
+      Level::INFO => tracing::info!($($arg)+),
+      Level::DEBUG => tracing::debug!($($arg)+),
+      Level::TRACE => tracing::trace!($($arg)+),
+    }
+  };
+}
+
+impl<API, E, Req, F, G> ImplsMethod<API, Req> for ApiTracer<E, F, G>
 where
-  Err: Debug, // TODO: use Display?
   E: Send + Sync,
   E: ImplsMethod<API, Req>,
-  Req: Send + Debug,
-  Res: Debug,
-  API: IsApi + HasMethod<Req, Res = Result<Res, Err>>,
+  Req: Send,
+  API: IsApi + HasMethod<Req>,
+  API::Res: Outcome,
+  F: TraceFormat<Req> + TraceFormat<<API::Res as Outcome>::Ok>,
+  G: TraceFormat<<API::Res as Outcome>::Err>,
 {
-  #[rustfmt::skip]
-  #[tracing::instrument(skip_all, fields(API = API::API_NAME, method = API::METHOD_NAME))]
-  async fn call_api(&self, req: Req) -> Result<Res, Err> {
-    let when = &self.0;
-    if when.request { tracing::debug!("Request: {req:?}") }
-    self.1.call_api(req).await
-      .inspect(|r| if when.response {tracing::debug!("Response: {r:?}")})
-      .inspect_err(|e| if when.error {tracing::error!("{e:?}")})
+  async fn call_api(&self, req: Req) -> API::Res {
+    let ApiTracer(config, inner) = self;
+    let span = tracing::info_span!(
+      "call_api",
+      "rpc.system" = "aisil",
+      "rpc.service" = API::API_NAME,
+      "rpc.method" = API::METHOD_NAME,
+      "otel.status_code" = Empty,
+      latency_ms = Empty,
+    );
+    async {
+      if let Some(level) = config.request {
+        event!(level, "Request: {}", Formatted::<F, _>::new(&req))
+      }
+      let started = Instant::now();
+      let res = inner.call_api(req).await;
+      span.record("latency_ms", started.elapsed().as_secs_f64() * 1000.0);
+      let outcome = res.as_result();
+      match (outcome, config.response, config.error) {
+        (Ok(ok), Some(level), _) => event!(level, "Response: {}", Formatted::<F, _>::new(ok)),
+        (Err(err), _, Some(level)) => event!(level, "{}", Formatted::<G, _>::new(err)),
+        _ => {}
+      }
+      span.record("otel.status_code", if outcome.is_err() { "ERROR" } else { "OK" });
+      res
+    }
+    .instrument(span.clone())
+    .await
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::CallApi;
+  use crate::test::*;
+  use std::sync::{Arc, Mutex};
+  use tracing::field::{Field, Visit};
+  use tracing::span::{Attributes, Id, Record};
+  use tracing::{Event, Metadata, Subscriber};
+
+  impl Redact for GetA {}
+
+  impl Redact for PostA {
+    fn redact(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+      f.write_str("PostA(***)")
+    }
+  }
+
+  /// Collects `name=value` of all span and event fields.
+  #[derive(Clone, Default)]
+  struct Capture(Arc<Mutex<Vec<String>>>);
+
+  impl Visit for Capture {
+    fn record_str(&mut self, field: &Field, value: &str) {
+      self.0.lock().unwrap().push(format!("{}={value}", field.name()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+      self.0.lock().unwrap().push(format!("{}={value:?}", field.name()));
+    }
+  }
+
+  impl Subscriber for Capture {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+      true
+    }
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+      span.record(&mut self.clone());
+      Id::from_u64(1)
+    }
+    fn record(&self, _: &Id, values: &Record<'_>) {
+      values.record(&mut self.clone());
+    }
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+    fn event(&self, event: &Event<'_>) {
+      event.record(&mut self.clone());
+    }
+    fn enter(&self, _: &Id) {}
+    fn exit(&self, _: &Id) {}
+  }
+
+  #[tokio::test]
+  async fn api_tracer() {
+    let capture = Capture::default();
+    let _guard = tracing::subscriber::set_default(capture.clone());
+    let config = ApiTracerConfig::new().request(Level::INFO).response(Level::DEBUG);
+    let config = config.error(Level::WARN).redact().display_errors();
+    let backend = ApiTracer(config, SomeBackend::default());
+    backend.call_api_x::<SomeAPI, _>(PostA(true)).await.unwrap();
+    assert!(backend.call_api_x::<SomeAPI, _>(PostA(true)).await.is_err());
+    assert!(backend.call_api_x::<SomeAPI, _>(GetA).await);
+
+    let fields = capture.0.lock().unwrap().clone();
+    let expected = [
+      "rpc.system=aisil",
+      "rpc.service=SomeAPI",
+      "rpc.method=post_a",
+      "message=Request: PostA(***)",
+      "message=Response: ()",
+      "message=can't post `a` anymore",
+      "otel.status_code=ERROR",
+      "rpc.method=get_a",
+      "message=Response: true",
+      "otel.status_code=OK",
+    ];
+    for field in expected {
+      assert!(fields.iter().any(|f| f == field), "missing {field:?} in {fields:?}");
+    }
+    assert!(fields.iter().any(|f| f.starts_with("latency_ms=")));
+    assert!(!fields.iter().any(|f| f.contains("PostA(true)")));
+
+    capture.0.lock().unwrap().clear();
+    let config = ApiTracerConfig::new().request(Level::INFO);
+    let backend = ApiTracer(config, SomeBackend::default());
+    backend.call_api_x::<SomeAPI, _>(PostA(true)).await.unwrap();
+    assert!(backend.call_api_x::<SomeAPI, _>(PostA(true)).await.is_err());
+    let fields = capture.0.lock().unwrap().clone();
+    let expected = ["message=Request: PostA(true)", r#"message="can't post `a` anymore""#];
+    for field in expected {
+      assert!(fields.iter().any(|f| f == field), "missing {field:?} in {fields:?}");
+    }
   }
 }
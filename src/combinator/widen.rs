@@ -0,0 +1,83 @@
+//! API combinators widening method errors, shared by implementor
+//! combinators that may reject calls.
+
+/// Define API combinator `$api` widening errors of methods returning
+/// `Result` into `$error<Err>`, an enum of the given variants and `Err(Err)`
+/// with the error of the method. `fmt` displays the given variants and
+/// `retry_after` overrides [`crate::HasMethod::retry_after`].
+macro_rules! widen_errors {
+  (
+    $(#[$api_meta:meta])*
+    $api:ident;
+    $(#[$error_meta:meta])*
+    $error:ident { $($variants:tt)* }
+    fmt($f:ident) { $($pat:pat => $fmt:expr),* $(,)? }
+    $(retry_after($res:ident) $retry_after:block)?
+  ) => {
+    $(#[$api_meta])*
+    ///
+    /// **API** combinator.
+    #[repr(transparent)]
+    pub struct $api<API>(pub API);
+
+    impl<API: $crate::IsApi> $crate::IsApi for $api<API> {
+      type Methods = API::Methods;
+      const API_NAME: &str = API::API_NAME;
+      const API_VERSION: &str = API::API_VERSION;
+    }
+
+    impl<API: documented::DocumentedOpt> documented::DocumentedOpt for $api<API> {
+      const DOCS: Option<&str> = API::DOCS;
+    }
+
+    impl<M, R, Err, API> $crate::HasMethod<M> for $api<API>
+    where
+      API: $crate::HasMethod<M, Res = Result<R, Err>>,
+    {
+      type Res = Result<R, $error<Err>>;
+      const METHOD_NAME: &str = API::METHOD_NAME;
+      const METHOD_DOCS: Option<&str> = API::METHOD_DOCS;
+
+      $(
+        fn retry_after($res: &Self::Res) -> Option<core::time::Duration> $retry_after
+      )?
+    }
+
+    impl<M, API: $crate::MethodIndex<M>> $crate::MethodIndex<M> for $api<API> {
+      type Index = API::Index;
+    }
+
+    $(#[$error_meta])*
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    pub enum $error<Err> {
+      $($variants)*
+      /// Error of the method.
+      Err(Err),
+    }
+
+    impl<Err> From<Err> for $error<Err> {
+      fn from(err: Err) -> Self {
+        $error::Err(err)
+      }
+    }
+
+    impl<Err: core::fmt::Display> core::fmt::Display for $error<Err> {
+      fn fmt(&self, $f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+          $($pat => $fmt,)*
+          $error::Err(err) => err.fmt($f),
+        }
+      }
+    }
+
+    impl<Err: std::error::Error + 'static> std::error::Error for $error<Err> {
+      fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+          $error::Err(err) => Some(err),
+          _ => None,
+        }
+      }
+    }
+  };
+}
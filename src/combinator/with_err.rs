@@ -1,15 +1,28 @@
 //! Wraps API method responses into `Result`.
 
-use crate::{HasMethod, IsApi};
+use crate::{HasMethod, ImplsMethod, IsApi, MethodIndex};
 use core::marker::PhantomData;
 use documented::DocumentedOpt;
 
 /// Wraps API method responses into `Result`.
 ///
-/// **API** combinator.
+/// Both **API** combinator and **implementor** combinator, the implementor
+/// never fails, e.g. to use a local implementor in place of a client.
 #[repr(transparent)]
 pub struct WithErr<Err, B>(pub B, PhantomData<Err>);
 
+impl<Err, B> WithErr<Err, B> {
+  pub fn new(b: B) -> Self {
+    Self(b, PhantomData)
+  }
+}
+
+impl<Err, B: Clone> Clone for WithErr<Err, B> {
+  fn clone(&self) -> Self {
+    Self::new(self.0.clone())
+  }
+}
+
 impl<API: IsApi, Err> IsApi for WithErr<Err, API> {
   type Methods = API::Methods;
   const API_NAME: &str = API::API_NAME;
@@ -22,6 +35,35 @@ impl<API: IsApi + HasMethod<M>, M, Err> HasMethod<M> for WithErr<Err, API> {
   const METHOD_DOCS: Option<&str> = API::METHOD_DOCS;
 }
 
+impl<API: MethodIndex<M>, M, Err> MethodIndex<M> for WithErr<Err, API> {
+  type Index = API::Index;
+}
+
 impl<Err, API: DocumentedOpt> DocumentedOpt for WithErr<Err, API> {
   const DOCS: Option<&str> = API::DOCS;
 }
+
+impl<API, M, B, Err> ImplsMethod<WithErr<Err, API>, M> for WithErr<Err, B>
+where
+  API: IsApi + HasMethod<M>,
+  B: ImplsMethod<API, M> + Sync,
+  Err: Sync,
+  M: Send,
+{
+  async fn call_api(&self, req: M) -> Result<API::Res, Err> {
+    Ok(self.0.call_api(req).await)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::CallApi;
+  use crate::test::*;
+
+  #[tokio::test]
+  async fn with_err() {
+    let backend = WithErr::<(), _>::new(SomeBackend::default());
+    assert_eq!(backend.call_api_x::<WithErr<(), SomeAPI>, _>(GetA).await, Ok(false));
+  }
+}
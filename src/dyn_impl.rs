@@ -0,0 +1,134 @@
+//! Type-erased implementor of a whole API.
+
+use std::sync::Arc;
+
+use crate::{ErasedMethod, ErasedMethods, GetMethod, HasMethod, ImplsMethod, IsApi, MethodIndex};
+
+/// Cloneable type-erased implementor of `API`, e.g. to select a backend at
+/// runtime.
+///
+/// Unlike [`crate::BoxedImpl`] it doesn't need the `ImplsApiNameBoxed` trait
+/// alias and can be constructed generically from any implementor of all of
+/// the methods.
+///
+/// ```ignore
+/// let backend: DynImpl<SomeAPI> = if mock {
+///   DynImpl::new(MockBackend)
+/// } else {
+///   DynImpl::new(SomeBackend::default())
+/// };
+/// let router = mk_post_json_router::<SomeAPI, _>().with_state(backend);
+/// ```
+pub struct DynImpl<API: IsApi>
+where
+  API::Methods: ErasedMethods<API>,
+{
+  methods: Arc<<API::Methods as ErasedMethods<API>>::List>,
+}
+
+impl<API: IsApi> DynImpl<API>
+where
+  API::Methods: ErasedMethods<API>,
+{
+  pub fn new<E>(implementor: E) -> Self
+  where
+    E: Send + Sync + 'static,
+    API::Methods: MkDynImpl<API, E>,
+  {
+    Self { methods: Arc::new(API::Methods::erase(&Arc::new(implementor))) }
+  }
+}
+
+impl<API: IsApi> Clone for DynImpl<API>
+where
+  API::Methods: ErasedMethods<API>,
+{
+  fn clone(&self) -> Self {
+    Self { methods: self.methods.clone() }
+  }
+}
+
+impl<API: IsApi> core::fmt::Debug for DynImpl<API>
+where
+  API::Methods: ErasedMethods<API>,
+{
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_struct("DynImpl").finish_non_exhaustive()
+  }
+}
+
+impl<API, M> ImplsMethod<API, M> for DynImpl<API>
+where
+  API: HasMethod<M> + MethodIndex<M>,
+  API::Methods: ErasedMethods<API>,
+  <API::Methods as ErasedMethods<API>>::List: GetMethod<API, M, API::Index>,
+  M: Send,
+{
+  async fn call_api(&self, req: M) -> API::Res {
+    self.methods.get_method().call(req).await
+  }
+}
+
+/// API method list traversal trait for erasing each method of `E`.
+///
+/// Use [`DynImpl::new`].
+pub trait MkDynImpl<API, E>: ErasedMethods<API> {
+  fn erase(implementor: &Arc<E>) -> Self::List;
+}
+
+impl<API, H, T, E> MkDynImpl<API, E> for (H, T)
+where
+  API: HasMethod<H> + 'static,
+  H: Send + 'static,
+  E: ImplsMethod<API, H> + Send + Sync + 'static,
+  T: MkDynImpl<API, E>,
+{
+  fn erase(implementor: &Arc<E>) -> Self::List {
+    let e = implementor.clone();
+    let method = ErasedMethod::new(move |req| {
+      let e = e.clone();
+      async move { ImplsMethod::<API, H>::call_api(&*e, req).await }
+    });
+    (method, T::erase(implementor))
+  }
+}
+
+impl<API, E> MkDynImpl<API, E> for () {
+  fn erase(_implementor: &Arc<E>) {}
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test::*;
+
+  #[tokio::test]
+  async fn dyn_impl() {
+    #[derive(Clone)]
+    struct Mock;
+    impl ImplsMethod<SomeAPI, GetA> for Mock {
+      async fn call_api(&self, _: GetA) -> bool {
+        true
+      }
+    }
+    impl ImplsMethod<SomeAPI, PostA> for Mock {
+      async fn call_api(&self, _: PostA) -> Res<()> {
+        Err("mock".to_owned())
+      }
+    }
+
+    fn assert_bounds<T: Clone + Send + Sync + 'static>(_: &T) {}
+
+    for mock in [false, true] {
+      let backend: DynImpl<SomeAPI> =
+        if mock { DynImpl::new(Mock) } else { DynImpl::new(SomeBackend::default()) };
+      assert_bounds(&backend);
+      assert_eq!(backend.clone().call_api(PostA(true)).await.is_err(), mock);
+      assert!(backend.call_api(GetA).await);
+    }
+
+    #[cfg(feature = "post-json-axum")]
+    let _: axum::Router = crate::server::post_json::mk_post_json_router::<SomeAPI, _>()
+      .with_state(DynImpl::<SomeAPI>::new(Mock));
+  }
+}
@@ -0,0 +1,57 @@
+//! Type-erased method implementations shared by [`crate::DynImpl`] and
+//! [`crate::func::FnImpl`].
+
+use core::pin::Pin;
+
+use crate::{HasMethod, Here, There};
+
+type BoxedFuture<API, M> = Pin<Box<dyn Future<Output = <API as HasMethod<M>>::Res> + Send>>;
+
+/// Boxed implementation of method `M` of `API`.
+pub struct ErasedMethod<API: HasMethod<M>, M>(Box<dyn Fn(M) -> BoxedFuture<API, M> + Send + Sync>);
+
+impl<API: HasMethod<M>, M> ErasedMethod<API, M> {
+  pub(crate) fn new<F, Fut>(f: F) -> Self
+  where
+    F: Fn(M) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = API::Res> + Send + 'static,
+  {
+    Self(Box::new(move |req| Box::pin(f(req))))
+  }
+
+  pub(crate) fn call(&self, req: M) -> BoxedFuture<API, M> {
+    (self.0)(req)
+  }
+}
+
+/// API method list traversal trait for the list of [`ErasedMethod`]s of all
+/// of the methods.
+pub trait ErasedMethods<API> {
+  type List: Send + Sync;
+}
+
+impl<API: HasMethod<H>, H, T: ErasedMethods<API>> ErasedMethods<API> for (H, T) {
+  type List = (ErasedMethod<API, H>, T::List);
+}
+
+impl<API> ErasedMethods<API> for () {
+  type List = ();
+}
+
+/// Lookup of the [`ErasedMethod`] of `M` at index `I` of [`ErasedMethods`],
+/// see [`crate::MethodIndex`].
+pub trait GetMethod<API: HasMethod<M>, M, I> {
+  fn get_method(&self) -> &ErasedMethod<API, M>;
+}
+
+impl<API: HasMethod<M>, M, T> GetMethod<API, M, Here> for (ErasedMethod<API, M>, T) {
+  fn get_method(&self) -> &ErasedMethod<API, M> {
+    &self.0
+  }
+}
+
+impl<API: HasMethod<M>, M, X, T: GetMethod<API, M, I>, I> GetMethod<API, M, There<I>> for (X, T) {
+  fn get_method(&self) -> &ErasedMethod<API, M> {
+    self.1.get_method()
+  }
+}
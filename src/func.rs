@@ -1,26 +1,235 @@
-// could be useful some day
+//! Closures and plain async functions as method implementors.
+//!
+//! See [`FnImpl`].
 
-impl <X, R, E> IsApi for fn(X) -> Result<R, E> {
-  type Methods = build_hlist!(X);
-    const DESCRIPTION: &str = "Anonimous function";
-    const NAME: &str = "fn";
+use core::marker::PhantomData;
+use std::sync::Arc;
+
+use crate::{
+  ErasedMethod, ErasedMethods, GetMethod, HasMethod, Here, ImplsMethod, IsApi, MethodIndex, There,
+};
+
+/// Implementor of `API` made of one async closure per method.
+///
+/// ```
+/// use aisil::{ImplsMethod, func::FnImpl};
+///
+/// pub struct SomeAPI;
+/// aisil::define_api! { pub SomeAPI => {
+///   "get_a", GetA => bool;
+///   "post_a", PostA => Result<(), ()>;
+/// } }
+///
+/// pub struct GetA;
+/// pub struct PostA(pub bool);
+///
+/// async fn post_a(PostA(a): PostA) -> Result<(), ()> {
+///   a.then_some(()).ok_or(())
+/// }
+///
+/// # fn main() {
+/// let backend = FnImpl::<SomeAPI>::builder()
+///   .on(|GetA| async { true })
+///   .on(post_a)
+///   .build();
+/// let _ = backend.call_api(PostA(true));
+/// # }
+/// ```
+///
+/// A missing method is a compile error:
+///
+/// ```compile_fail
+/// # pub struct SomeAPI;
+/// # aisil::define_api! { pub SomeAPI => {
+/// #   "get_a", GetA => bool;
+/// #   "post_a", PostA => Result<(), ()>;
+/// # } }
+/// # pub struct GetA;
+/// # pub struct PostA(pub bool);
+/// # fn main() {
+/// let backend = aisil::func::FnImpl::<SomeAPI>::builder()
+///   .on(|GetA| async { true })
+///   .build();
+/// # }
+/// ```
+///
+/// So is implementing a method twice:
+///
+/// ```compile_fail
+/// # pub struct SomeAPI;
+/// # aisil::define_api! { pub SomeAPI => {
+/// #   "get_a", GetA => bool;
+/// #   "post_a", PostA => Result<(), ()>;
+/// # } }
+/// # pub struct GetA;
+/// # pub struct PostA(pub bool);
+/// # fn main() {
+/// let backend = aisil::func::FnImpl::<SomeAPI>::builder()
+///   .on(|GetA| async { true })
+///   .on(|GetA| async { false })
+///   .on(|PostA(_)| async { Ok(()) })
+///   .build();
+/// # }
+/// ```
+pub struct FnImpl<API: IsApi>
+where
+  API::Methods: ErasedMethods<API>,
+{
+  methods: Arc<<API::Methods as ErasedMethods<API>>::List>,
 }
 
-impl <X, R, E> ApiMethod<fn(X) -> Result<R, E>> for fn(X) -> Result<R, E> {
-  type Res = R;
-    const DESCRIPTION: &str = "Anonimous function";
-    const NAME: &str = "fn";
+impl<API: IsApi> FnImpl<API>
+where
+  API::Methods: ErasedMethods<API> + VacantMethods,
+{
+  pub fn builder() -> FnImplBuilder<API, <API::Methods as VacantMethods>::Slots> {
+    FnImplBuilder { slots: API::Methods::vacant(), marker: PhantomData }
+  }
 }
 
-impl <X, R, E> ImplsApi<fn(X) -> Result<R, E>> for fn(X) -> Result<R, E> {
-  type Err = E;
+impl<API: IsApi> Clone for FnImpl<API>
+where
+  API::Methods: ErasedMethods<API>,
+{
+  fn clone(&self) -> Self {
+    Self { methods: self.methods.clone() }
+  }
 }
 
-impl<X, R, E> ImplsMethod<fn(X) -> Result<R, E>, X>
-    for fn(X) -> Result<R, E>
-    where X: ApiMethod<Self, Res = R> + Send
+impl<API: IsApi> core::fmt::Debug for FnImpl<API>
+where
+  API::Methods: ErasedMethods<API>,
 {
-    async fn call_api(&self, req: X) -> Result<R, E> {
-        self(req)
-    }
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.debug_struct("FnImpl").finish_non_exhaustive()
+  }
+}
+
+impl<API, M> ImplsMethod<API, M> for FnImpl<API>
+where
+  API: HasMethod<M> + MethodIndex<M>,
+  API::Methods: ErasedMethods<API>,
+  <API::Methods as ErasedMethods<API>>::List: GetMethod<API, M, API::Index>,
+  M: Send,
+{
+  async fn call_api(&self, req: M) -> API::Res {
+    self.methods.get_method().call(req).await
+  }
+}
+
+/// Builder of [`FnImpl`], `Slots` is the list of [`ErasedMethod`]s of the
+/// implemented methods and [`Vacant`] methods.
+pub struct FnImplBuilder<API, Slots> {
+  slots: Slots,
+  marker: PhantomData<fn() -> API>,
+}
+
+impl<API: IsApi, Slots> FnImplBuilder<API, Slots> {
+  /// Implement method `M` with an async closure or function. Fails to compile
+  /// if `M` is already implemented.
+  pub fn on<M, F, Fut>(self, f: F) -> FnImplBuilder<API, Slots::Filled>
+  where
+    API: HasMethod<M> + MethodIndex<M>,
+    Slots: Fill<API, M, API::Index>,
+    F: Fn(M) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = API::Res> + Send + 'static,
+  {
+    FnImplBuilder { slots: self.slots.fill(ErasedMethod::new(f)), marker: PhantomData }
+  }
+
+  /// Finish the implementor, fails to compile unless every method of `API`
+  /// is implemented.
+  pub fn build(self) -> FnImpl<API>
+  where
+    Slots: Implemented<API>,
+    API::Methods: ErasedMethods<API, List = Slots>,
+  {
+    FnImpl { methods: Arc::new(self.slots) }
+  }
+}
+
+/// Not yet implemented method `M`.
+pub struct Vacant<M>(PhantomData<fn() -> M>);
+
+/// API method list traversal trait for the [`Vacant`] methods of a new
+/// [`FnImplBuilder`].
+pub trait VacantMethods {
+  type Slots;
+  fn vacant() -> Self::Slots;
+}
+
+impl<H, T: VacantMethods> VacantMethods for (H, T) {
+  type Slots = (Vacant<H>, T::Slots);
+  fn vacant() -> Self::Slots {
+    (Vacant(PhantomData), T::vacant())
+  }
+}
+
+impl VacantMethods for () {
+  type Slots = ();
+  fn vacant() {}
+}
+
+/// Slots with the [`Vacant`] method `M` at index `I` implemented.
+#[diagnostic::on_unimplemented(message = "method `{M}` is already implemented")]
+pub trait Fill<API: HasMethod<M>, M, I> {
+  type Filled;
+  fn fill(self, method: ErasedMethod<API, M>) -> Self::Filled;
+}
+
+impl<API: HasMethod<M>, M, T> Fill<API, M, Here> for (Vacant<M>, T) {
+  type Filled = (ErasedMethod<API, M>, T);
+  fn fill(self, method: ErasedMethod<API, M>) -> Self::Filled {
+    (method, self.1)
+  }
+}
+
+impl<API: HasMethod<M>, M, X, T: Fill<API, M, I>, I> Fill<API, M, There<I>> for (X, T) {
+  type Filled = (X, T::Filled);
+  fn fill(self, method: ErasedMethod<API, M>) -> Self::Filled {
+    (self.0, self.1.fill(method))
+  }
+}
+
+/// Slots without [`Vacant`] methods.
+#[diagnostic::on_unimplemented(
+  message = "not every method of `{API}` is implemented",
+  label = "missing `.on(..)` for a `Vacant` method of `{Self}`"
+)]
+pub trait Implemented<API> {}
+
+impl<API: HasMethod<H>, H, T: Implemented<API>> Implemented<API> for (ErasedMethod<API, H>, T) {}
+
+impl<API> Implemented<API> for () {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test::*;
+  use std::sync::atomic::{AtomicBool, Ordering};
+
+  #[tokio::test]
+  async fn fn_impl() {
+    let a = Arc::new(AtomicBool::new(false));
+    let backend = FnImpl::<SomeAPI>::builder()
+      .on({
+        let a = a.clone();
+        move |PostA(new_a)| {
+          let a = a.clone();
+          async move {
+            (!a.swap(new_a, Ordering::SeqCst)).then_some(()).ok_or("already posted".to_owned())
+          }
+        }
+      })
+      .on(move |GetA| {
+        let a = a.clone();
+        async move { a.load(Ordering::SeqCst) }
+      })
+      .build();
+
+    assert!(!backend.call_api(GetA).await);
+    backend.clone().call_api(PostA(true)).await.unwrap();
+    assert!(backend.call_api(GetA).await);
+    assert!(backend.call_api(PostA(true)).await.is_err());
+  }
 }
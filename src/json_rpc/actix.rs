@@ -0,0 +1,70 @@
+//! Make a JsonRPC server with actix-web.
+//!
+//! See [`json_rpc_handler`] and [`json_rpc_route`].
+
+use actix_web::http::StatusCode;
+use actix_web::web::{self, Data, Payload};
+use actix_web::{HttpResponse, Route};
+
+use super::server::{JsonRpcRequest, JsonRpcResponse, MkJsonRpcRouter, json_rpc_router};
+use crate::IsApi;
+
+/// actix-web handler for JsonRPC requests, the implementor is taken from
+/// `web::Data<E>` app data. Request bodies are limited to 2MB, see
+/// [`json_rpc_route`] for a custom limit.
+///
+/// ```ignore
+/// App::new()
+///   .app_data(Data::new(SomeBackend::default()))
+///   .route("/rpc", web::post().to(json_rpc_handler::<SomeAPI, SomeBackend>))
+/// ```
+pub async fn json_rpc_handler<API, E>(svc: Data<E>, payload: Payload) -> HttpResponse
+where
+  API: IsApi,
+  API::Methods: MkJsonRpcRouter<API, E>,
+  E: Sync,
+{
+  handle::<API, E>(svc, payload, 2 * 1024 * 1024).await
+}
+
+/// Same as [`json_rpc_handler`] as a `POST` route with a maximum request body
+/// size in bytes.
+///
+/// ```ignore
+/// App::new()
+///   .app_data(Data::new(SomeBackend::default()))
+///   .route("/rpc", json_rpc_route::<SomeAPI, SomeBackend>(64 * 1024))
+/// ```
+pub fn json_rpc_route<API, E>(body_limit: usize) -> Route
+where
+  API: IsApi + 'static,
+  API::Methods: MkJsonRpcRouter<API, E>,
+  E: Sync + Send + 'static,
+{
+  web::post().to(move |svc: Data<E>, payload: Payload| handle::<API, E>(svc, payload, body_limit))
+}
+
+async fn handle<API, E>(svc: Data<E>, payload: Payload, body_limit: usize) -> HttpResponse
+where
+  API: IsApi,
+  API::Methods: MkJsonRpcRouter<API, E>,
+  E: Sync,
+{
+  let body = match payload.to_bytes_limited(body_limit).await {
+    Ok(Ok(body)) => body,
+    Ok(Err(err)) => {
+      let res = JsonRpcResponse::invalid_request(format!("Failed to read request body: {err}"));
+      return HttpResponse::BadRequest().json(res);
+    }
+    Err(_) => {
+      let res =
+        JsonRpcResponse::invalid_request(format!("Request is larger than {body_limit} bytes"));
+      return HttpResponse::build(StatusCode::PAYLOAD_TOO_LARGE).json(res);
+    }
+  };
+  let res = match JsonRpcRequest::parse(&body) {
+    Ok(req) => json_rpc_router::<API, E>(&svc, req).await,
+    Err(res) => res,
+  };
+  HttpResponse::Ok().json(res)
+}
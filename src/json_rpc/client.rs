@@ -1,58 +1,103 @@
 //! Call API as JsonRPC.
 
-use crate::{HasMethod, ImplsMethod, IsApi, combinator::WithErr};
-use core::marker::PhantomData;
-use reqwest::{Client, Error, Method, Url};
-use serde::{Deserialize, Serialize};
+use crate::client::{Client, ClientTransport};
+use crate::post_json::codec::CodecError;
+use http::Request;
+use http::header::CONTENT_TYPE;
+use reqwest::{Method, Url};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
 
-/// Wrapper over [`reqwest::Client`] with fixed base URL.
-pub struct JsonRpcClient<API> {
+pub use crate::client::{
+  CallOptions, ClientError, HttpBackend, HttpHooks, ReqwestBackend, TokenProvider,
+};
+
+/// [`Client`] calling APIs as JsonRPC.
+pub type JsonRpcClient<API, B = ReqwestBackend> = Client<API, JsonRpcTransport<B>>;
+
+/// Wrapper over an [`HttpBackend`] with fixed URL.
+#[derive(Debug, Clone)]
+pub struct JsonRpcTransport<B = ReqwestBackend> {
   method: Method,
   base_url: Url,
-  client: Client,
-  api_marker: PhantomData<API>,
+  backend: B,
+  hooks: HttpHooks,
 }
 
-impl<API> Clone for JsonRpcClient<API> {
-  fn clone(&self) -> Self {
-    JsonRpcClient {
-      method: self.method.clone(),
-      base_url: self.base_url.clone(),
-      client: self.client.clone(),
-      api_marker: PhantomData,
-    }
+impl<B: HttpBackend> ClientTransport for JsonRpcTransport<B> {
+  fn encode<Req: Serialize>(
+    &self,
+    method_name: &'static str,
+    req: &Req,
+  ) -> Result<Vec<u8>, ClientError> {
+    encode_request(method_name, req)
+  }
+
+  async fn send(
+    &self,
+    method_name: &'static str,
+    body: Vec<u8>,
+    options: &CallOptions,
+  ) -> Result<Vec<u8>, ClientError> {
+    let mk_request = || {
+      let request = Request::builder().method(self.method.clone()).uri(self.base_url.as_str());
+      request.header(CONTENT_TYPE, "application/json").body(body.clone()).unwrap()
+    };
+    let response = self.hooks.send(&self.backend, method_name, options, mk_request).await?;
+    Ok(response.into_body())
   }
+
+  fn decode<Res: DeserializeOwned>(
+    &self,
+    _: &'static str,
+    body: &[u8],
+  ) -> Result<Res, ClientError> {
+    decode_response(body)
+  }
+}
+
+/// Wrap a request of method `method_name` into a JsonRPC envelope.
+pub(crate) fn encode_request<Req: Serialize>(
+  method_name: &'static str,
+  req: &Req,
+) -> Result<Vec<u8>, ClientError> {
+  let req =
+    JsonRpcRequest { method: method_name, params: SingleParam { payload: req }, jsonrpc: "2.0" };
+  serde_json::to_vec(&req).map_err(|err| ClientError::Codec(CodecError::new(err)))
 }
 
-impl<API, Req, Res> ImplsMethod<WithErr<Error, API>, Req> for JsonRpcClient<API>
-where
-  API: IsApi + HasMethod<Req, Res = Res> + Send + Sync,
-  Req: serde::Serialize + Send,
-  Res: serde::de::DeserializeOwned,
-{
-  async fn call_api(&self, req: Req) -> Result<Res, Error> {
-    Ok(
-      self
-        .client
-        .request(self.method.clone(), self.base_url.clone())
-        .json(&JsonRpcRequest {
-          method: API::METHOD_NAME,
-          params: SingleParam { payload: req },
-          jsonrpc: "2.0",
-        })
-        .send()
-        .await?
-        .error_for_status()?
-        .json::<JsonRpcResponse<Res>>()
-        .await?
-        .result,
-    )
+/// Unwrap the result of a JsonRPC response, error objects are
+/// [`ClientError::Rpc`].
+pub(crate) fn decode_response<Res: DeserializeOwned>(body: &[u8]) -> Result<Res, ClientError> {
+  let codec_err = |err| ClientError::Codec(CodecError::new(err));
+  match serde_json::from_slice(body).map_err(codec_err)? {
+    JsonRpcResponse { error: Some(JsonRpcError { code, message }), .. } => {
+      Err(ClientError::Rpc { code, message })
+    }
+    JsonRpcResponse { result, .. } => serde_json::from_value(result).map_err(codec_err),
   }
 }
 
 impl<API> JsonRpcClient<API> {
-  pub fn new(method: Method, base_url: Url, client: Client) -> Self {
-    Self { method, base_url, client, api_marker: PhantomData }
+  pub fn new(method: Method, base_url: Url, client: reqwest::Client) -> Self {
+    Self::with_backend(method, base_url, ReqwestBackend(client))
+  }
+}
+
+impl<API, B> JsonRpcClient<API, B> {
+  /// Same as [`JsonRpcClient::new`] but with a custom [`HttpBackend`].
+  pub fn with_backend(method: Method, base_url: Url, backend: B) -> Self {
+    Client::from_transport(JsonRpcTransport {
+      method,
+      base_url,
+      backend,
+      hooks: HttpHooks::default(),
+    })
+  }
+
+  /// Hooks applied to every request.
+  pub fn with_hooks(self, hooks: HttpHooks) -> Self {
+    self.map_transport(|t| JsonRpcTransport { hooks, ..t })
   }
 }
 
@@ -69,6 +114,14 @@ struct JsonRpcRequest<'a, P> {
 }
 
 #[derive(Debug, Clone, Deserialize)]
-struct JsonRpcResponse<X> {
-  result: X,
+struct JsonRpcResponse {
+  #[serde(default)]
+  result: Value,
+  error: Option<JsonRpcError>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct JsonRpcError {
+  code: i32,
+  message: String,
 }
@@ -0,0 +1,96 @@
+//! Make a JsonRPC server with plain `hyper`, without `axum`.
+//!
+//! See [`JsonRpcService`].
+
+use core::convert::Infallible;
+use core::marker::PhantomData;
+use core::pin::Pin;
+use http_body_util::{BodyExt, Full, LengthLimitError, Limited};
+use hyper::body::{Body, Bytes};
+use hyper::header::{CONTENT_TYPE, HeaderValue};
+use hyper::{Method, Request, Response, StatusCode};
+
+use super::server::{JsonRpcRequest, JsonRpcResponse, MkJsonRpcRouter, json_rpc_router};
+use crate::IsApi;
+
+/// [`hyper::service::Service`] handling JsonRPC requests posted to any path.
+///
+/// ```ignore
+/// let svc = JsonRpcService::<SomeAPI, _>::new(SomeBackend::default());
+/// http1::Builder::new().serve_connection(TokioIo::new(stream), svc).await
+/// ```
+pub struct JsonRpcService<API, E> {
+  implementor: E,
+  body_limit: usize,
+  api_marker: PhantomData<fn() -> API>,
+}
+
+impl<API, E: Clone> Clone for JsonRpcService<API, E> {
+  fn clone(&self) -> Self {
+    Self {
+      implementor: self.implementor.clone(),
+      body_limit: self.body_limit,
+      api_marker: PhantomData,
+    }
+  }
+}
+
+impl<API, E> JsonRpcService<API, E> {
+  pub fn new(implementor: E) -> Self {
+    Self { implementor, body_limit: 2 * 1024 * 1024, api_marker: PhantomData }
+  }
+
+  /// Maximum request body size in bytes, default: 2MB.
+  pub fn with_body_limit(self, body_limit: usize) -> Self {
+    Self { body_limit, ..self }
+  }
+}
+
+impl<API, E, B> hyper::service::Service<Request<B>> for JsonRpcService<API, E>
+where
+  API: IsApi + 'static,
+  API::Methods: MkJsonRpcRouter<API, E>,
+  E: Clone + Send + Sync + 'static,
+  B: Body + Send + 'static,
+  B::Data: Send,
+  B::Error: std::error::Error + Send + Sync + 'static,
+{
+  type Response = Response<Full<Bytes>>;
+  type Error = Infallible;
+  type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Infallible>> + Send>>;
+
+  fn call(&self, req: Request<B>) -> Self::Future {
+    let implementor = self.implementor.clone();
+    let body_limit = self.body_limit;
+    Box::pin(async move {
+      if req.method() != Method::POST {
+        let res = JsonRpcResponse::invalid_request("Only `POST` method is allowed");
+        return Ok(response(StatusCode::METHOD_NOT_ALLOWED, &res));
+      }
+      let body = match Limited::new(req.into_body(), body_limit).collect().await {
+        Ok(body) => body.to_bytes(),
+        Err(err) if err.is::<LengthLimitError>() => {
+          let res =
+            JsonRpcResponse::invalid_request(format!("Request is larger than {body_limit} bytes"));
+          return Ok(response(StatusCode::PAYLOAD_TOO_LARGE, &res));
+        }
+        Err(err) => {
+          let res = JsonRpcResponse::invalid_request(format!("Failed to read request body: {err}"));
+          return Ok(response(StatusCode::BAD_REQUEST, &res));
+        }
+      };
+      let res = match JsonRpcRequest::parse(&body) {
+        Ok(req) => json_rpc_router::<API, E>(&implementor, req).await,
+        Err(res) => res,
+      };
+      Ok(response(StatusCode::OK, &res))
+    })
+  }
+}
+
+fn response(status: StatusCode, res: &JsonRpcResponse) -> Response<Full<Bytes>> {
+  let mut response = Response::new(Full::new(Bytes::from(serde_json::to_vec(res).unwrap())));
+  *response.status_mut() = status;
+  response.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+  response
+}
@@ -1,7 +1,11 @@
 //! JsonRPC
 
+#[cfg(feature = "json-rpc-actix")]
+pub mod actix;
 #[cfg(feature = "client")]
 pub mod client;
+#[cfg(feature = "json-rpc-hyper")]
+pub mod hyper;
 #[cfg(feature = "json-rpc-openrpc")]
 pub mod openrpc;
 #[cfg(feature = "json-rpc-server")]
@@ -11,6 +15,7 @@ pub mod server;
 #[cfg(all(feature = "client", feature = "json-rpc-server"))]
 mod tests {
   #![allow(clippy::bool_assert_comparison)]
+  #[cfg(feature = "post-json-axum")]
   #[tokio::test]
   async fn axum_reqwest() {
     use axum::{Router, extract::Json, extract::State, routing::post};
@@ -54,4 +59,73 @@ mod tests {
 
     server_thread.abort();
   }
+  #[cfg(feature = "json-rpc-hyper")]
+  #[tokio::test]
+  async fn hyper_reqwest() {
+    use hyper_util::rt::TokioIo;
+    use std::net::Ipv4Addr;
+
+    use super::client::JsonRpcClient;
+    use super::hyper::JsonRpcService;
+    use crate::ImplsMethod;
+    use crate::test::*;
+
+    let svc = JsonRpcService::<SomeAPI, _>::new(SomeBackend::default());
+    let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server_thread = tokio::spawn(async move {
+      loop {
+        let (stream, _) = listener.accept().await.unwrap();
+        let conn = hyper::server::conn::http1::Builder::new()
+          .serve_connection(TokioIo::new(stream), svc.clone());
+        tokio::spawn(conn);
+      }
+    });
+    let url = reqwest::Url::parse(&format!("http://{addr}/rpc")).unwrap();
+
+    let client: JsonRpcClient<SomeAPI> =
+      JsonRpcClient::new(reqwest::Method::POST, url.clone(), reqwest::Client::new());
+    client.call_api(PostA(true)).await.unwrap().unwrap();
+    assert!(client.call_api(GetA).await.unwrap());
+
+    let call = async |body: &'static str| -> serde_json::Value {
+      let res = reqwest::Client::new().post(url.clone()).body(body).send().await.unwrap();
+      res.json().await.unwrap()
+    };
+    assert_eq!(call("{").await["error"]["code"], -32700);
+    assert_eq!(call(r#"{"id":1}"#).await["error"]["code"], -32600);
+
+    server_thread.abort();
+  }
+  #[cfg(feature = "json-rpc-actix")]
+  #[actix_web::test]
+  async fn actix() {
+    use actix_web::{App, test, web};
+
+    use super::actix::{json_rpc_handler, json_rpc_route};
+    use crate::test::*;
+
+    let app = App::new()
+      .app_data(web::Data::new(SomeBackend::default()))
+      .route("/rpc", web::post().to(json_rpc_handler::<SomeAPI, SomeBackend>))
+      .route("/small", json_rpc_route::<SomeAPI, SomeBackend>(8));
+    let app = test::init_service(app).await;
+
+    let call = async |body: &'static str| -> serde_json::Value {
+      let req = test::TestRequest::post().uri("/rpc").set_payload(body);
+      test::call_and_read_body_json(&app, req.to_request()).await
+    };
+    let res = call(r#"{"jsonrpc":"2.0","id":1,"method":"post_a","params":{"payload":true}}"#).await;
+    assert_eq!(res["result"], serde_json::json!({ "Ok": null }));
+    let res = call(r#"{"jsonrpc":"2.0","id":2,"method":"get_a","params":{"payload":null}}"#).await;
+    assert_eq!(res["result"], true);
+    assert_eq!(call("{").await["error"]["code"], -32700);
+    assert_eq!(call(r#"{"id":1}"#).await["error"]["code"], -32600);
+
+    let req = test::TestRequest::post().uri("/small").set_payload("[1, 2, 3, 4]");
+    let res = test::call_service(&app, req.to_request()).await;
+    assert_eq!(res.status(), 413);
+    let res: serde_json::Value = test::read_body_json(res).await;
+    assert_eq!(res["error"]["code"], -32600);
+  }
 }
@@ -76,6 +76,16 @@ pub struct JsonRpcRequest {
   // jsonrpc: Option<&'a str>,
 }
 
+impl JsonRpcRequest {
+  /// Parse a request body, answering with a parse error if it's not valid
+  /// JSON and with an invalid request error if it's not a JsonRPC request.
+  pub fn parse(body: &[u8]) -> Result<Self, JsonRpcResponse> {
+    let req: Value = serde_json::from_slice(body).map_err(JsonRpcResponse::parse_error)?;
+    serde_json::from_value(req)
+      .map_err(|err| JsonRpcResponse::invalid_request(format!("Invalid request: {err}")))
+  }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 struct SingleParam {
   payload: Box<RawValue>,
@@ -91,6 +101,23 @@ pub struct JsonRpcResponse {
   jsonrpc: Option<&'static str>,
 }
 
+impl JsonRpcResponse {
+  fn error(code: i32, message: String) -> Self {
+    let error = Some(JsonRpcError { code, message });
+    JsonRpcResponse { result: None, error, id: None, jsonrpc: Some("2.0") }
+  }
+
+  /// Response to a request that is not valid JSON.
+  pub fn parse_error(err: serde_json::Error) -> Self {
+    Self::error(-32700, format!("Parse error: {err}"))
+  }
+
+  /// Response to a request that is not a valid JsonRPC request.
+  pub fn invalid_request(message: impl Into<String>) -> Self {
+    Self::error(-32600, message.into())
+  }
+}
+
 #[derive(Clone, Debug, Serialize)]
 struct JsonRpcError {
   code: i32,
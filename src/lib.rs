@@ -34,25 +34,38 @@ pub mod combinator;
 mod json_rpc;
 mod post_json;
 
+#[cfg(feature = "loopback")]
+pub mod loopback;
+
 /// Utilities for exposing an API implementor as a server.
 pub mod server {
+  #[cfg(feature = "json-rpc-actix")]
+  pub use crate::json_rpc::actix as json_rpc_actix;
+  #[cfg(feature = "json-rpc-hyper")]
+  pub use crate::json_rpc::hyper as json_rpc_hyper;
   #[cfg(feature = "json-rpc-server")]
   pub use crate::json_rpc::server as json_rpc;
+  #[cfg(feature = "post-json-actix")]
+  pub use crate::post_json::actix as post_json_actix;
+  #[cfg(feature = "post-json-hyper")]
+  pub use crate::post_json::hyper as post_json_hyper;
   #[cfg(feature = "post-json-axum")]
   pub use crate::post_json::server as post_json;
 }
 
-/// Utilities for calling an API as a client.
 #[cfg(feature = "client")]
-pub mod client {
-  pub use crate::json_rpc::client as json_rpc;
-  pub use crate::post_json::client as post_json;
-}
+pub mod client;
 
 /// Utilities to generate specifications, IDLs, SDKs, etc.
 pub mod generate;
 
-// pub mod func;
+mod dyn_impl;
+pub use dyn_impl::*;
+
+mod erased;
+pub use erased::*;
+
+pub mod func;
 
 #[doc(hidden)]
 pub mod internal {
@@ -63,6 +76,7 @@ pub mod internal {
 mod test;
 
 use core::future::Future;
+use core::marker::PhantomData;
 use core::pin::Pin;
 
 /// API definition as a type. Use [`define_api`] macro to define this impl.
@@ -82,8 +96,30 @@ pub trait HasMethod<M>: IsApi {
   type Res;
   const METHOD_NAME: &str;
   const METHOD_DOCS: Option<&str>;
+
+  /// Time after which a rejected call may succeed when `res` is a rejection
+  /// of the call itself, e.g. by [`combinator::RateLimit`]. Servers answer it
+  /// with `429 Too Many Requests` instead of the response.
+  fn retry_after(_res: &Self::Res) -> Option<core::time::Duration> {
+    None
+  }
 }
 
+/// Position of method `M` in [`IsApi::Methods`], so that type-erased
+/// implementors like [`DynImpl`] look methods up at compile time.
+///
+/// Implemented by [`define_api`] and forwarded by API combinators.
+pub trait MethodIndex<M> {
+  /// [`Here`] or [`There`].
+  type Index;
+}
+
+/// Index of the head of a method list.
+pub struct Here;
+
+/// Index into the tail of a method list.
+pub struct There<I>(PhantomData<I>);
+
 /// Generalization over an asyncronous function bound by an API definition.
 /// Similar to `tower::Service` but associated types are defined in the API
 /// definition instead of the trait itself.
@@ -165,6 +201,25 @@ where
   }
 }
 
+/// API method list traversal trait for collecting method names.
+pub trait MethodNames<API> {
+  fn method_names() -> Vec<&'static str>;
+}
+
+impl<API: HasMethod<H>, H, T: MethodNames<API>> MethodNames<API> for (H, T) {
+  fn method_names() -> Vec<&'static str> {
+    let mut names = T::method_names();
+    names.insert(0, API::METHOD_NAME);
+    names
+  }
+}
+
+impl<API> MethodNames<API> for () {
+  fn method_names() -> Vec<&'static str> {
+    Vec::new()
+  }
+}
+
 /// Helper trait to allow pretty type applications that [`ImplsMethod`] does
 /// not allow. You may need it when one request belongs to two APIs and a
 /// backend implements both of them. You should not reuse requests in different
@@ -204,6 +259,18 @@ macro_rules! impl_method {
   };
 }
 
+#[macro_export]
+#[doc(hidden)]
+macro_rules! impl_method_index {
+  ($api:ty, $index:ty; ) => {};
+  ($api:ty, $index:ty; $req:ty $(, $rest:ty)*) => {
+    impl $crate::MethodIndex<$req> for $api {
+      type Index = $index;
+    }
+    $crate::impl_method_index!{$api, $crate::There<$index>; $($rest),*}
+  };
+}
+
 #[macro_export]
 #[doc(hidden)]
 macro_rules! build_hlist {
@@ -234,7 +301,7 @@ macro_rules! build_hlist {
 ///
 /// This macro generates
 /// - [`IsApi`] impl for the API type.
-/// - [`HasMethod`] impl for each request type.
+/// - [`HasMethod`] and [`MethodIndex`] impls for each request type.
 /// - Custom `ImplsApiName` trait alias with [`ImplsMethod`] supertraits for
 ///   each method. Useful for dependency inversion.
 /// - Custom `ImplsApiNameBoxed` trait alias with [`ImplsMethodBoxed`]
@@ -282,6 +349,8 @@ macro_rules! impl_is_api {
         const API_VERSION: &str = $version;
       }
 
+      $crate::impl_method_index!{$api, $crate::Here; $($req),+}
+
       $crate::internal::paste! {
         // creating custom macro for the api to give users more extendability powers
         #[allow(unused_macros)]
@@ -0,0 +1,255 @@
+//! In-process transport that round-trips requests and responses through the
+//! same serialization as the real transports, without opening sockets.
+//!
+//! Useful to test that an implementor is wire-compatible with its API:
+//!
+//! ```ignore
+//! let client = Loopback::<SomeAPI, _, JsonRpc>::new(SomeBackend::default());
+//! client.call_api(PostA(true)).await?.unwrap();
+//! ```
+
+use core::marker::PhantomData;
+use http::StatusCode;
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::client::{CallOptions, Client, ClientError, ClientTransport, json_rpc};
+use crate::json_rpc::server::{JsonRpcRequest, MkJsonRpcRouter, json_rpc_router};
+use crate::post_json::dispatch::DispatchPostJson;
+use crate::{HasMethod, ImplsMethod, IsApi, MethodNames};
+
+pub use crate::post_json::codec::{CodecError, Encoding};
+
+/// [`Client`] passing each call to `E` through codec `C`, see [`Json`],
+/// [`JsonRpc`] and [`PostJson`].
+///
+/// Implements `WithErr<ClientError, API>` and reports mismatches with the
+/// same [`ClientError`]s as the real clients.
+pub type Loopback<API, E, C = Json> = Client<API, LoopbackTransport<API, E, C>>;
+
+impl<API, E, C: Default> Loopback<API, E, C> {
+  pub fn new(implementor: E) -> Self {
+    Self::with_codec(implementor, C::default())
+  }
+}
+
+impl<API, E, C> Loopback<API, E, C> {
+  pub fn with_codec(implementor: E, codec: C) -> Self {
+    Client::from_transport(LoopbackTransport { implementor, codec, api_marker: PhantomData })
+  }
+}
+
+/// [`ClientTransport`] calling `E` in-process, see [`Loopback`].
+#[derive(Debug, Clone, Copy)]
+pub struct LoopbackTransport<API, E, C> {
+  implementor: E,
+  codec: C,
+  api_marker: PhantomData<fn() -> API>,
+}
+
+impl<API, E, C> LoopbackTransport<API, E, C> {
+  pub fn into_inner(self) -> E {
+    self.implementor
+  }
+}
+
+/// Plain JSON request and response bodies.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json;
+
+/// JsonRPC envelopes routed by [`json_rpc_router`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonRpc;
+
+/// HTTP `POST /<method_name>` semantics with the given body [`Encoding`],
+/// including transport level error responses.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PostJson(pub Encoding);
+
+fn no_method(method_name: &str) -> ClientError {
+  ClientError::Transport(format!("No method {method_name:?}").into())
+}
+
+impl<API, E> ClientTransport for LoopbackTransport<API, E, Json>
+where
+  API: IsApi,
+  API::Methods: LoopbackJson<API, E>,
+  E: Sync,
+{
+  async fn send(
+    &self,
+    method_name: &'static str,
+    body: Vec<u8>,
+    _: &CallOptions,
+  ) -> Result<Vec<u8>, ClientError> {
+    let res = API::Methods::call(&self.implementor, method_name, &body).await;
+    res.ok_or_else(|| no_method(method_name))?.map_err(ClientError::Codec)
+  }
+}
+
+impl<API, E> ClientTransport for LoopbackTransport<API, E, JsonRpc>
+where
+  API: IsApi,
+  API::Methods: MkJsonRpcRouter<API, E>,
+  E: Sync,
+{
+  fn encode<Req: Serialize>(
+    &self,
+    method_name: &'static str,
+    req: &Req,
+  ) -> Result<Vec<u8>, ClientError> {
+    json_rpc::encode_request(method_name, req)
+  }
+
+  async fn send(
+    &self,
+    _: &'static str,
+    body: Vec<u8>,
+    _: &CallOptions,
+  ) -> Result<Vec<u8>, ClientError> {
+    let res = match JsonRpcRequest::parse(&body) {
+      Ok(req) => json_rpc_router::<API, E>(&self.implementor, req).await,
+      Err(res) => res,
+    };
+    serde_json::to_vec(&res).map_err(|err| ClientError::Codec(CodecError::new(err)))
+  }
+
+  fn decode<Res: DeserializeOwned>(
+    &self,
+    _: &'static str,
+    body: &[u8],
+  ) -> Result<Res, ClientError> {
+    json_rpc::decode_response(body)
+  }
+}
+
+impl<API, E> ClientTransport for LoopbackTransport<API, E, PostJson>
+where
+  API: IsApi,
+  API::Methods: DispatchPostJson<API, E>,
+  E: Sync,
+{
+  fn encode<Req: Serialize>(&self, _: &'static str, req: &Req) -> Result<Vec<u8>, ClientError> {
+    self.codec.0.encode(req).map_err(ClientError::Codec)
+  }
+
+  async fn send(
+    &self,
+    method_name: &'static str,
+    body: Vec<u8>,
+    _: &CallOptions,
+  ) -> Result<Vec<u8>, ClientError> {
+    let mime = Some(self.codec.0.mime());
+    let reply = API::Methods::dispatch(&self.implementor, method_name, mime, mime, &body).await;
+    let reply = reply.ok_or_else(|| no_method(method_name))?;
+    if reply.status != 200 {
+      let status = StatusCode::from_u16(reply.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+      let is_json = reply.content_type == Encoding::Json.mime();
+      let body = is_json.then(|| serde_json::from_slice(&reply.body).ok()).flatten();
+      return Err(ClientError::Status { status, body });
+    }
+    Ok(reply.body)
+  }
+
+  fn decode<Res: DeserializeOwned>(
+    &self,
+    _: &'static str,
+    body: &[u8],
+  ) -> Result<Res, ClientError> {
+    self.codec.0.decode(body).map_err(ClientError::Codec)
+  }
+}
+
+/// API method list traversal trait for calling methods by name with plain
+/// JSON bodies.
+///
+/// Use [`Loopback`].
+pub trait LoopbackJson<API, E>: MethodNames<API> {
+  /// Call method by its name, `None` if there's no such method.
+  fn call(
+    svc: &E,
+    method_name: &str,
+    body: &[u8],
+  ) -> impl Future<Output = Option<Result<Vec<u8>, CodecError>>> + Send;
+}
+
+impl<API, E, H, T> LoopbackJson<API, E> for (H, T)
+where
+  API: HasMethod<H>,
+  API::Res: Serialize,
+  H: DeserializeOwned + Send,
+  E: ImplsMethod<API, H> + Sync,
+  T: LoopbackJson<API, E>,
+{
+  async fn call(svc: &E, method_name: &str, body: &[u8]) -> Option<Result<Vec<u8>, CodecError>> {
+    if method_name != API::METHOD_NAME {
+      return T::call(svc, method_name, body).await;
+    }
+    let req: H = match Encoding::Json.decode(body) {
+      Ok(req) => req,
+      Err(err) => return Some(Err(err)),
+    };
+    Some(Encoding::Json.encode(&svc.call_api(req).await))
+  }
+}
+
+impl<API, E: Sync> LoopbackJson<API, E> for () {
+  async fn call(_: &E, _: &str, _: &[u8]) -> Option<Result<Vec<u8>, CodecError>> {
+    None
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test::*;
+  use serde::Deserialize;
+
+  #[tokio::test]
+  async fn roundtrip() {
+    let json = Loopback::<SomeAPI, _, Json>::new(SomeBackend::default());
+    json.call_api(PostA(true)).await.unwrap().unwrap();
+    assert!(json.call_api(GetA).await.unwrap());
+
+    let json_rpc = Loopback::<SomeAPI, _, JsonRpc>::new(SomeBackend::default());
+    json_rpc.call_api(PostA(true)).await.unwrap().unwrap();
+    assert!(json_rpc.call_api(GetA).await.unwrap());
+
+    for encoding in Encoding::all() {
+      let post_json =
+        Loopback::<SomeAPI, _, _>::with_codec(SomeBackend::default(), PostJson(encoding));
+      post_json.call_api(PostA(true)).await.unwrap().unwrap();
+      assert!(post_json.call_api(GetA).await.unwrap());
+    }
+  }
+
+  #[tokio::test]
+  async fn mismatch() {
+    struct MismatchAPI;
+    crate::define_api! { MismatchAPI => {
+      "get", Get => bool;
+    } }
+
+    #[derive(Serialize, Deserialize)]
+    struct Get {
+      #[serde(skip_serializing)]
+      _a: bool,
+    }
+
+    #[derive(Clone, Copy)]
+    struct Backend;
+    impl ImplsMethod<MismatchAPI, Get> for Backend {
+      async fn call_api(&self, _: Get) -> bool {
+        true
+      }
+    }
+
+    let req = || Get { _a: true };
+    let res = Loopback::<MismatchAPI, _, Json>::new(Backend).call_api(req()).await;
+    assert!(matches!(res, Err(ClientError::Codec(_))));
+    let res = Loopback::<MismatchAPI, _, JsonRpc>::new(Backend).call_api(req()).await;
+    assert!(matches!(res, Err(ClientError::Rpc { code: -32602, .. })));
+    let res = Loopback::<MismatchAPI, _, PostJson>::new(Backend).call_api(req()).await;
+    let Err(ClientError::Status { status, body: Some(body) }) = res else { panic!("{res:?}") };
+    assert_eq!((status.as_u16(), body.code), (422, 422));
+  }
+}
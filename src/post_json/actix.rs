@@ -0,0 +1,103 @@
+//! Make a server as HTTP `POST /<method_name>` with actix-web.
+//!
+//! See [`configure_post_json`].
+
+use actix_web::http::{StatusCode, header};
+use actix_web::web::{self, Data, Payload, ServiceConfig};
+use actix_web::{HttpRequest, HttpResponse, Responder, Scope};
+use serde::{Serialize, de::DeserializeOwned};
+use std::sync::Arc;
+
+use super::dispatch::{Reply, call_method};
+use crate::{HasMethod, ImplsMethod, IsApi, MethodNames};
+
+pub use super::codec::{CodecError, Encoding};
+pub use super::dispatch::{ErrorBody, PostJsonConfig};
+pub use super::route::{PostJsonRoutes, RouteCase};
+
+/// Configures an actix `App` or `Scope` with one `POST /<method_name>` route
+/// per method, with the same semantics and error responses as the axum
+/// router, except that compression is not supported, use actix `Compress`
+/// middleware instead.
+///
+/// The implementor is taken from `web::Data<E>` app data.
+///
+/// ```ignore
+/// App::new()
+///   .app_data(Data::new(SomeBackend::default()))
+///   .configure(configure_post_json::<SomeAPI, SomeBackend>(PostJsonConfig::default()))
+/// ```
+pub fn configure_post_json<API: IsApi, E>(
+  config: impl Into<PostJsonConfig>,
+) -> impl Fn(&mut ServiceConfig) + Clone
+where
+  API::Methods: MkPostJsonActix<API, E>,
+{
+  let config = Arc::new(config.into());
+  config.route_table::<API>();
+  #[cfg(feature = "post-json-compression")]
+  assert!(!config.compression, "compression is not supported by actix, use `Compress` middleware");
+  move |cfg| API::Methods::configure(cfg, &config)
+}
+
+/// Same as [`configure_post_json`] but mounted as a `Scope` at `path`.
+pub fn mk_post_json_scope<API: IsApi, E>(path: &str, config: impl Into<PostJsonConfig>) -> Scope
+where
+  API::Methods: MkPostJsonActix<API, E>,
+{
+  web::scope(path).configure(configure_post_json::<API, E>(config))
+}
+
+/// API method list traversal trait for configuring an actix route for each
+/// method.
+///
+/// Use [`configure_post_json`].
+pub trait MkPostJsonActix<API, E>: MethodNames<API> {
+  fn configure(cfg: &mut ServiceConfig, config: &PostJsonConfig);
+}
+
+impl<API, E, H, T> MkPostJsonActix<API, E> for (H, T)
+where
+  API: HasMethod<H> + 'static,
+  API::Res: Serialize,
+  H: DeserializeOwned + 'static,
+  E: ImplsMethod<API, H> + 'static,
+  T: MkPostJsonActix<API, E>,
+{
+  fn configure(cfg: &mut ServiceConfig, config: &PostJsonConfig) {
+    let limit = config.method_body_limit_of(API::METHOD_NAME);
+    let handler = move |svc: Data<E>, req: HttpRequest, payload: Payload| async move {
+      let body = match payload.to_bytes_limited(limit).await {
+        Ok(Ok(body)) => body,
+        Ok(Err(err)) => return Reply::error(400, format!("Failed to read request body: {err}")),
+        Err(_) => return Reply::payload_too_large(limit),
+      };
+      let header = |name| req.headers().get(name).and_then(|v| v.to_str().ok());
+      let (content_type, accept) = (header(header::CONTENT_TYPE), header(header::ACCEPT));
+      call_method::<API, H, E>(&svc, content_type, accept, &body).await
+    };
+    cfg.service(
+      web::resource(config.routes.path(API::METHOD_NAME))
+        .route(web::post().to(handler))
+        .default_service(web::to(async || Reply::method_not_allowed())),
+    );
+    T::configure(cfg, config);
+  }
+}
+
+impl<API, E> MkPostJsonActix<API, E> for () {
+  fn configure(_cfg: &mut ServiceConfig, _config: &PostJsonConfig) {}
+}
+
+impl Responder for Reply {
+  type Body = actix_web::body::BoxBody;
+
+  fn respond_to(self, _req: &HttpRequest) -> HttpResponse {
+    let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    let mut res = HttpResponse::build(status);
+    if let Some(secs) = self.retry_after {
+      res.insert_header((actix_web::http::header::RETRY_AFTER, secs));
+    }
+    res.content_type(self.content_type).body(self.body)
+  }
+}
@@ -1,53 +1,138 @@
 //! Call API as HTTP `POST /<method_name>` with JSON bodies.
 
-use crate::{HasMethod, ImplsMethod, IsApi, combinator::WithErr};
-use core::marker::PhantomData;
-use reqwest::{Client, Error, Url};
+use crate::client::{Client, ClientTransport};
+use http::header::{ACCEPT, CONTENT_TYPE};
+#[cfg(feature = "post-json-compression")]
+use http::header::{ACCEPT_ENCODING, CONTENT_ENCODING};
+use http::{Method, Request};
+use reqwest::Url;
+use serde::{Serialize, de::DeserializeOwned};
 
-/// Wrapper over [`reqwest::Client`] with fixed base URL.
+pub use super::codec::{CodecError, Encoding};
+#[cfg(feature = "post-json-compression")]
+pub use super::compression::ContentCoding;
+pub use super::route::{PostJsonRoutes, RouteCase};
+pub use crate::client::{
+  CallOptions, ClientError, HttpBackend, HttpHooks, ReqwestBackend, TokenProvider,
+};
+
+/// [`Client`] calling APIs as `POST /<method_name>`, routes relative to the
+/// base URL are built with [`PostJsonRoutes`].
 ///
-/// Calls APIs as `POST /<method_name>`
-pub struct PostJsonClient<API> {
+/// With `post-json-compression` feature compressed responses are decompressed
+/// transparently and requests can be compressed, see
+/// [`PostJsonClient::with_compression`].
+pub type PostJsonClient<API, B = ReqwestBackend> = Client<API, PostJsonTransport<B>>;
+
+/// Wrapper over an [`HttpBackend`] with fixed base URL.
+#[derive(Debug, Clone)]
+pub struct PostJsonTransport<B = ReqwestBackend> {
   base_url: Url,
-  client: Client,
-  api_marker: PhantomData<API>,
+  backend: B,
+  routes: PostJsonRoutes,
+  encoding: Encoding,
+  hooks: HttpHooks,
+  #[cfg(feature = "post-json-compression")]
+  compression: Option<ContentCoding>,
 }
 
-impl<API> Clone for PostJsonClient<API> {
-  fn clone(&self) -> Self {
-    PostJsonClient {
-      base_url: self.base_url.clone(),
-      client: self.client.clone(),
-      api_marker: PhantomData,
+impl<B: HttpBackend> ClientTransport for PostJsonTransport<B> {
+  fn encode<Req: Serialize>(&self, _: &'static str, req: &Req) -> Result<Vec<u8>, ClientError> {
+    let body = self.encoding.encode(req).map_err(ClientError::Codec)?;
+    #[cfg(feature = "post-json-compression")]
+    if let Some(coding) = self.compression {
+      return coding.compress(&body).map_err(|err| ClientError::Compression(err.into()));
     }
+    Ok(body)
   }
-}
 
-impl<API, Req, Res> ImplsMethod<WithErr<Error, API>, Req> for PostJsonClient<API>
-where
-  API: IsApi + HasMethod<Req, Res = Res> + Send + Sync,
-  Req: serde::Serialize + Send,
-  Res: serde::de::DeserializeOwned,
-{
-  async fn call_api(&self, req: Req) -> Result<Res, Error> {
-    self
-      .client
-      .post(self.base_url.join(API::METHOD_NAME).unwrap())
-      .json(&req)
-      .send()
-      .await?
-      .error_for_status()?
-      .json::<Res>()
-      .await
+  async fn send(
+    &self,
+    method_name: &'static str,
+    body: Vec<u8>,
+    options: &CallOptions,
+  ) -> Result<Vec<u8>, ClientError> {
+    let mut url = self.base_url.clone();
+    // `new` rejects base URLs that can't have a path
+    if let Ok(mut segments) = url.path_segments_mut() {
+      segments.pop_if_empty().extend(self.routes.path(method_name).split('/').skip(1));
+    }
+    let mk_request = || {
+      let request = Request::builder()
+        .method(Method::POST)
+        .uri(url.as_str())
+        .header(CONTENT_TYPE, self.encoding.mime())
+        .header(ACCEPT, self.encoding.mime());
+      #[cfg(feature = "post-json-compression")]
+      let request = match self.compression {
+        Some(coding) => request.header(CONTENT_ENCODING, coding.name()),
+        None => request,
+      }
+      .header(ACCEPT_ENCODING, "gzip, zstd, br");
+      request.body(body.clone()).unwrap()
+    };
+    let response = self.hooks.send(&self.backend, method_name, options, mk_request).await?;
+    #[cfg(feature = "post-json-compression")]
+    if let Some(content_encoding) = response.headers().get(CONTENT_ENCODING) {
+      let content_encoding =
+        content_encoding.to_str().map_err(|e| ClientError::Compression(e.into()))?;
+      return super::compression::decompress(content_encoding, response.body(), usize::MAX)
+        .map_err(|err| ClientError::Compression(err.into()));
+    }
+    Ok(response.into_body())
+  }
+
+  fn decode<Res: DeserializeOwned>(
+    &self,
+    _: &'static str,
+    body: &[u8],
+  ) -> Result<Res, ClientError> {
+    self.encoding.decode(body).map_err(ClientError::Codec)
   }
 }
 
 impl<API> PostJsonClient<API> {
-  pub fn new(base_url: Url, client: Client) -> Option<Self> {
-    (!base_url.cannot_be_a_base()).then_some(PostJsonClient {
-      base_url,
-      client,
-      api_marker: PhantomData,
+  pub fn new(base_url: Url, client: reqwest::Client) -> Option<Self> {
+    Self::with_backend(base_url, ReqwestBackend(client))
+  }
+}
+
+impl<API, B> PostJsonClient<API, B> {
+  /// Same as [`PostJsonClient::new`] but with a custom [`HttpBackend`].
+  pub fn with_backend(base_url: Url, backend: B) -> Option<Self> {
+    (!base_url.cannot_be_a_base()).then(|| {
+      Client::from_transport(PostJsonTransport {
+        base_url,
+        backend,
+        routes: PostJsonRoutes::default(),
+        encoding: Encoding::default(),
+        hooks: HttpHooks::default(),
+        #[cfg(feature = "post-json-compression")]
+        compression: None,
+      })
     })
   }
+
+  /// Use custom routes, must match the ones used by the server.
+  pub fn with_routes(self, routes: PostJsonRoutes) -> Self {
+    self.map_transport(|t| PostJsonTransport { routes, ..t })
+  }
+
+  /// Encoding of request and response bodies, default: [`Encoding::Json`].
+  pub fn with_encoding(self, encoding: Encoding) -> Self {
+    self.map_transport(|t| PostJsonTransport { encoding, ..t })
+  }
+
+  /// Compress request bodies, default: no compression. The server must
+  /// support the coding, e.g. the axum router with
+  /// `PostJsonConfig::compression` enabled.
+  #[cfg(feature = "post-json-compression")]
+  pub fn with_compression(self, compression: ContentCoding) -> Self {
+    self.map_transport(|t| PostJsonTransport { compression: Some(compression), ..t })
+  }
+
+  /// Hooks applied to every request.
+  pub fn with_hooks(self, hooks: HttpHooks) -> Self {
+    self.map_transport(|t| PostJsonTransport { hooks, ..t })
+  }
 }
@@ -0,0 +1,222 @@
+//! Body encodings of HTTP `POST /<method_name>`.
+
+use core::fmt;
+use serde::{Serialize, de::DeserializeOwned};
+
+/// JSON body of transport level error responses.
+#[cfg(any(
+  feature = "client",
+  feature = "post-json-actix",
+  feature = "post-json-axum",
+  feature = "post-json-hyper",
+  feature = "loopback"
+))]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, serde::Deserialize)]
+pub struct ErrorBody {
+  /// HTTP status code
+  pub code: u16,
+  /// Human readable description
+  pub message: String,
+}
+
+/// Body encoding. Requests are decoded according to `Content-Type`, responses
+/// are encoded according to `Accept`.
+///
+/// JSON is always available, other encodings are enabled by
+/// `post-json-cbor` and `post-json-msgpack` features.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+  /// `application/json`, default.
+  #[default]
+  Json,
+  /// `application/cbor`
+  #[cfg(feature = "post-json-cbor")]
+  Cbor,
+  /// `application/msgpack`
+  #[cfg(feature = "post-json-msgpack")]
+  MsgPack,
+}
+
+impl Encoding {
+  /// All encodings enabled by crate features.
+  pub fn all() -> Vec<Encoding> {
+    #[allow(unused_mut)]
+    let mut all = vec![Encoding::Json];
+    #[cfg(feature = "post-json-cbor")]
+    all.push(Encoding::Cbor);
+    #[cfg(feature = "post-json-msgpack")]
+    all.push(Encoding::MsgPack);
+    all
+  }
+
+  /// Media type of the encoding.
+  pub const fn mime(self) -> &'static str {
+    match self {
+      Encoding::Json => "application/json",
+      #[cfg(feature = "post-json-cbor")]
+      Encoding::Cbor => "application/cbor",
+      #[cfg(feature = "post-json-msgpack")]
+      Encoding::MsgPack => "application/msgpack",
+    }
+  }
+
+  /// Parse `Content-Type` header value, parameters like `charset` are ignored.
+  /// Structured syntax suffixes like `application/problem+json` are accepted.
+  pub fn from_mime(mime: &str) -> Option<Encoding> {
+    let essence = mime.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    let suffix = essence.strip_prefix("application/").and_then(|s| s.rsplit_once('+'));
+    match suffix.map_or(essence.as_str(), |(_, suffix)| suffix) {
+      "json" => return Some(Encoding::Json),
+      #[cfg(feature = "post-json-cbor")]
+      "cbor" => return Some(Encoding::Cbor),
+      _ => {}
+    }
+    match essence.as_str() {
+      "application/json" => Some(Encoding::Json),
+      #[cfg(feature = "post-json-cbor")]
+      "application/cbor" => Some(Encoding::Cbor),
+      #[cfg(feature = "post-json-msgpack")]
+      "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => {
+        Some(Encoding::MsgPack)
+      }
+      _ => None,
+    }
+  }
+
+  /// Pick response encoding according to `Accept` header value. Missing
+  /// header and wildcards resolve to `default`, which is usually the request
+  /// encoding. Returns `None` if none of the accepted media types is
+  /// supported.
+  pub fn negotiate(accept: Option<&str>, default: Encoding) -> Option<Encoding> {
+    let Some(accept) = accept.filter(|a| !a.trim().is_empty()) else {
+      return Some(default);
+    };
+    let mut ranges: Vec<(&str, f32)> = accept
+      .split(',')
+      .map(|range| {
+        let mut parts = range.split(';').map(str::trim);
+        let mime = parts.next().unwrap_or_default();
+        let q =
+          parts.filter_map(|p| p.strip_prefix("q=")).find_map(|q| q.parse().ok()).unwrap_or(1.0);
+        (mime, q)
+      })
+      .filter(|(_, q)| *q > 0.0)
+      .collect();
+    ranges.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    ranges.into_iter().find_map(|(mime, _)| match mime {
+      "*/*" | "application/*" => Some(default),
+      mime => Encoding::from_mime(mime),
+    })
+  }
+
+  /// Serialize a value.
+  pub fn encode<T: Serialize + ?Sized>(self, value: &T) -> Result<Vec<u8>, CodecError> {
+    match self {
+      Encoding::Json => serde_json::to_vec(value).map_err(CodecError::new),
+      #[cfg(feature = "post-json-cbor")]
+      Encoding::Cbor => {
+        let mut buf = Vec::new();
+        ciborium::into_writer(value, &mut buf).map_err(CodecError::new)?;
+        Ok(buf)
+      }
+      #[cfg(feature = "post-json-msgpack")]
+      Encoding::MsgPack => rmp_serde::to_vec_named(value).map_err(CodecError::new),
+    }
+  }
+
+  /// Deserialize a value. See [`CodecError::is_data`] for telling malformed
+  /// bodies from bodies of a wrong shape.
+  pub fn decode<T: DeserializeOwned>(self, body: &[u8]) -> Result<T, CodecError> {
+    match self {
+      Encoding::Json => serde_json::from_slice(body).map_err(|err| {
+        let data = err.classify() == serde_json::error::Category::Data;
+        CodecError { data, ..CodecError::new(err) }
+      }),
+      #[cfg(feature = "post-json-cbor")]
+      Encoding::Cbor => ciborium::from_reader(body).map_err(|err| {
+        let data = matches!(err, ciborium::de::Error::Semantic(..));
+        CodecError { data, ..CodecError::new(err) }
+      }),
+      #[cfg(feature = "post-json-msgpack")]
+      Encoding::MsgPack => rmp_serde::from_slice(body).map_err(|err| {
+        use rmp_serde::decode::Error;
+        let data = matches!(
+          err,
+          Error::TypeMismatch(_) | Error::OutOfRange | Error::LengthMismatch(_) | Error::Syntax(_)
+        );
+        CodecError { data, ..CodecError::new(err) }
+      }),
+    }
+  }
+}
+
+impl fmt::Display for Encoding {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.mime())
+  }
+}
+
+/// Serialization or deserialization error of an [`Encoding`].
+#[derive(Debug)]
+pub struct CodecError {
+  err: Box<dyn std::error::Error + Send + Sync>,
+  data: bool,
+}
+
+impl CodecError {
+  /// Wrap an error of a custom encoding, e.g. to build
+  /// `ClientError::Codec` in a custom transport.
+  pub fn new(err: impl std::error::Error + Send + Sync + 'static) -> Self {
+    Self { err: Box::new(err), data: false }
+  }
+
+  /// The body is well-formed but doesn't match the expected type, e.g. has a
+  /// missing field. Such requests are answered with 422 instead of 400.
+  pub fn is_data(&self) -> bool {
+    self.data
+  }
+}
+
+impl fmt::Display for CodecError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    self.err.fmt(f)
+  }
+}
+
+impl std::error::Error for CodecError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    Some(self.err.as_ref())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn negotiate() {
+    use Encoding::Json;
+    assert_eq!(Encoding::from_mime("application/json; charset=utf-8"), Some(Json));
+    assert_eq!(Encoding::from_mime("application/problem+json"), Some(Json));
+    assert_eq!(Encoding::from_mime("application/vnd.api+JSON; charset=utf-8"), Some(Json));
+    assert_eq!(Encoding::from_mime("text/plain"), None);
+    assert_eq!(Encoding::from_mime("text/plain+json"), None);
+    assert_eq!(Encoding::negotiate(None, Json), Some(Json));
+    assert_eq!(Encoding::negotiate(Some("*/*"), Json), Some(Json));
+    assert_eq!(Encoding::negotiate(Some("text/html, application/json;q=0.5"), Json), Some(Json));
+    assert_eq!(Encoding::negotiate(Some("text/html"), Json), None);
+    assert_eq!(Encoding::negotiate(Some("application/json;q=0"), Json), None);
+  }
+
+  #[test]
+  fn roundtrip() {
+    let value: Result<(Option<bool>, String), u8> = Ok((Some(true), "a".into()));
+    for encoding in Encoding::all() {
+      let bytes = encoding.encode(&value).unwrap();
+      assert_eq!(encoding.decode::<Result<(Option<bool>, String), u8>>(&bytes).unwrap(), value);
+      assert!(encoding.decode::<bool>(&bytes).unwrap_err().is_data());
+      let truncated = encoding.decode::<Result<(Option<bool>, String), u8>>(&bytes[..3]);
+      assert!(!truncated.unwrap_err().is_data());
+    }
+  }
+}
@@ -0,0 +1,138 @@
+//! `Content-Encoding` of HTTP `POST /<method_name>` bodies.
+
+use core::fmt;
+use std::io::{self, Read, Write};
+
+/// Compression of request and response bodies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentCoding {
+  /// `gzip`
+  Gzip,
+  /// `zstd`
+  Zstd,
+  /// `br`
+  Brotli,
+}
+
+impl ContentCoding {
+  /// Name of the coding in `Content-Encoding` and `Accept-Encoding` headers.
+  pub const fn name(self) -> &'static str {
+    match self {
+      ContentCoding::Gzip => "gzip",
+      ContentCoding::Zstd => "zstd",
+      ContentCoding::Brotli => "br",
+    }
+  }
+
+  /// Parse a coding name, `None` for unsupported ones.
+  pub fn from_name(name: &str) -> Option<ContentCoding> {
+    match name.trim().to_ascii_lowercase().as_str() {
+      "gzip" | "x-gzip" => Some(ContentCoding::Gzip),
+      "zstd" => Some(ContentCoding::Zstd),
+      "br" => Some(ContentCoding::Brotli),
+      _ => None,
+    }
+  }
+
+  /// Pick response coding according to `Accept-Encoding` header value,
+  /// `None` means no compression.
+  #[cfg(feature = "post-json-hyper")]
+  pub(crate) fn negotiate(accept_encoding: &str) -> Option<ContentCoding> {
+    let mut codings: Vec<(ContentCoding, f32)> = accept_encoding
+      .split(',')
+      .filter_map(|coding| {
+        let mut parts = coding.split(';').map(str::trim);
+        let coding = ContentCoding::from_name(parts.next().unwrap_or_default())?;
+        let q =
+          parts.filter_map(|p| p.strip_prefix("q=")).find_map(|q| q.parse().ok()).unwrap_or(1.0);
+        Some((coding, q))
+      })
+      .filter(|(_, q)| *q > 0.0)
+      .collect();
+    codings.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    codings.first().map(|(coding, _)| *coding)
+  }
+
+  pub(crate) fn compress(self, body: &[u8]) -> io::Result<Vec<u8>> {
+    match self {
+      ContentCoding::Gzip => {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        encoder.write_all(body)?;
+        encoder.finish()
+      }
+      ContentCoding::Zstd => zstd::stream::encode_all(body, 0),
+      ContentCoding::Brotli => {
+        let mut buf = Vec::new();
+        brotli::CompressorReader::new(body, 4096, 5, 22).read_to_end(&mut buf)?;
+        Ok(buf)
+      }
+    }
+  }
+
+  fn decompress(self, body: &[u8], limit: usize) -> io::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    let limit = limit.saturating_add(1) as u64;
+    match self {
+      ContentCoding::Gzip => flate2::read::GzDecoder::new(body).take(limit).read_to_end(&mut buf),
+      ContentCoding::Zstd => {
+        zstd::stream::read::Decoder::new(body)?.take(limit).read_to_end(&mut buf)
+      }
+      ContentCoding::Brotli => {
+        brotli::Decompressor::new(body, 4096).take(limit).read_to_end(&mut buf)
+      }
+    }?;
+    Ok(buf)
+  }
+}
+
+impl fmt::Display for ContentCoding {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.name())
+  }
+}
+
+/// Decompress a body according to `Content-Encoding` header value. Codings
+/// listed there are applied in order, so they are undone in reverse.
+///
+/// Fails with [`io::ErrorKind::Unsupported`] on unknown codings and with
+/// [`io::ErrorKind::FileTooLarge`] when the result is longer than `limit`.
+pub(crate) fn decompress(content_encoding: &str, body: &[u8], limit: usize) -> io::Result<Vec<u8>> {
+  let mut body = body.to_vec();
+  for name in content_encoding.rsplit(',').map(str::trim) {
+    if name.is_empty() || name.eq_ignore_ascii_case("identity") {
+      continue;
+    }
+    let Some(coding) = ContentCoding::from_name(name) else {
+      let msg = format!("unsupported content encoding {name:?}");
+      return Err(io::Error::new(io::ErrorKind::Unsupported, msg));
+    };
+    body = coding.decompress(&body, limit)?;
+    if body.len() > limit {
+      let msg = format!("decompressed body is larger than {limit} bytes");
+      return Err(io::Error::new(io::ErrorKind::FileTooLarge, msg));
+    }
+  }
+  Ok(body)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn roundtrip() {
+    let body = b"a".repeat(1000);
+    for coding in [ContentCoding::Gzip, ContentCoding::Zstd, ContentCoding::Brotli] {
+      let compressed = coding.compress(&body).unwrap();
+      assert!(compressed.len() < body.len());
+      assert_eq!(decompress(coding.name(), &compressed, 1000).unwrap(), body);
+      let err = decompress(coding.name(), &compressed, 999).unwrap_err();
+      assert_eq!(err.kind(), io::ErrorKind::FileTooLarge);
+    }
+    let gzip_br = ContentCoding::Gzip.compress(&body).unwrap();
+    let gzip_br = ContentCoding::Brotli.compress(&gzip_br).unwrap();
+    assert_eq!(decompress("gzip, identity, br", &gzip_br, usize::MAX).unwrap(), body);
+    let err = decompress("compress", &body, usize::MAX).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+  }
+}
@@ -0,0 +1,244 @@
+//! Framework agnostic part of HTTP `POST /<method_name>` servers: config,
+//! content negotiation and error responses.
+
+use serde::{Serialize, de::DeserializeOwned};
+#[cfg(any(
+  feature = "post-json-actix",
+  feature = "post-json-axum",
+  feature = "post-json-hyper"
+))]
+use {super::route::PostJsonRoutes, crate::IsApi, crate::MethodNames, std::collections::HashMap};
+
+use super::codec::Encoding;
+pub use super::codec::ErrorBody;
+use crate::{HasMethod, ImplsMethod};
+
+/// Request body limit used when none is configured, same as axum's default.
+#[cfg(any(feature = "post-json-actix", feature = "post-json-hyper"))]
+pub(crate) const DEFAULT_BODY_LIMIT: usize = 2 * 1024 * 1024;
+
+/// Server configuration shared by all `post_json` servers.
+#[cfg(any(feature = "post-json-actix", feature = "post-json-axum", feature = "post-json-hyper"))]
+#[derive(Debug, Clone, Default)]
+pub struct PostJsonConfig {
+  pub(crate) routes: PostJsonRoutes,
+  pub(crate) body_limit: Option<usize>,
+  pub(crate) method_body_limits: HashMap<String, usize>,
+  #[cfg(feature = "post-json-compression")]
+  pub(crate) compression: bool,
+}
+
+#[cfg(any(feature = "post-json-actix", feature = "post-json-axum", feature = "post-json-hyper"))]
+impl From<PostJsonRoutes> for PostJsonConfig {
+  fn from(routes: PostJsonRoutes) -> Self {
+    Self { routes, ..Self::default() }
+  }
+}
+
+#[cfg(any(feature = "post-json-actix", feature = "post-json-axum", feature = "post-json-hyper"))]
+impl PostJsonConfig {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Routes of API methods, default: `/<method_name>`.
+  pub fn routes(self, routes: PostJsonRoutes) -> Self {
+    Self { routes, ..self }
+  }
+
+  /// Maximum request body size in bytes for all methods, default: 2MB.
+  /// Larger requests are rejected with `413 Payload Too Large` and a JSON
+  /// [`ErrorBody`].
+  pub fn body_limit(self, limit: usize) -> Self {
+    Self { body_limit: Some(limit), ..self }
+  }
+
+  /// Maximum request body size in bytes for a single method, overrides
+  /// [`PostJsonConfig::body_limit`]. Servers panic on start if there's no
+  /// method with this name.
+  pub fn method_body_limit(mut self, method_name: impl Into<String>, limit: usize) -> Self {
+    self.method_body_limits.insert(method_name.into(), limit);
+    self
+  }
+
+  /// Compress responses with gzip, zstd or brotli according to
+  /// `Accept-Encoding` and decompress requests according to
+  /// `Content-Encoding`, default: `false`. Supported by the axum router and
+  /// the hyper service, actix configuration panics with it, use actix
+  /// `Compress` middleware instead.
+  #[cfg(feature = "post-json-compression")]
+  pub fn compression(self, compression: bool) -> Self {
+    Self { compression, ..self }
+  }
+
+  /// Routes of all methods of an API.
+  ///
+  /// # Panics
+  ///
+  /// When two methods are mapped to the same route or a body limit is set
+  /// for a method the API doesn't have.
+  pub(crate) fn route_table<API>(&self) -> HashMap<String, &'static str>
+  where
+    API: IsApi,
+    API::Methods: MethodNames<API>,
+  {
+    let table = self.routes.table::<API>();
+    for name in self.method_body_limits.keys() {
+      if !table.values().any(|m| m == name) {
+        panic!("body limit is set for `{name}` which is not a method of `{}`", API::API_NAME);
+      }
+    }
+    table
+  }
+
+  /// Effective request body limit of a method.
+  #[cfg(any(feature = "post-json-actix", feature = "post-json-hyper"))]
+  pub(crate) fn method_body_limit_of(&self, method_name: &str) -> usize {
+    let limit = self.method_body_limits.get(method_name).copied();
+    limit.or(self.body_limit).unwrap_or(DEFAULT_BODY_LIMIT)
+  }
+}
+
+/// Framework agnostic HTTP response.
+#[derive(Debug, Clone)]
+pub struct Reply {
+  pub(crate) status: u16,
+  pub(crate) content_type: &'static str,
+  pub(crate) body: Vec<u8>,
+  /// `Retry-After` header in seconds.
+  #[cfg(any(
+    feature = "post-json-actix",
+    feature = "post-json-axum",
+    feature = "post-json-hyper"
+  ))]
+  pub(crate) retry_after: Option<u64>,
+}
+
+impl Reply {
+  fn new(status: u16, content_type: &'static str, body: Vec<u8>) -> Self {
+    Reply {
+      status,
+      content_type,
+      body,
+      #[cfg(any(
+        feature = "post-json-actix",
+        feature = "post-json-axum",
+        feature = "post-json-hyper"
+      ))]
+      retry_after: None,
+    }
+  }
+
+  pub(crate) fn error(status: u16, message: impl Into<String>) -> Self {
+    let body = ErrorBody { code: status, message: message.into() };
+    Reply::new(status, Encoding::Json.mime(), serde_json::to_vec(&body).unwrap_or_default())
+  }
+
+  #[cfg(any(feature = "post-json-actix", feature = "post-json-hyper"))]
+  pub(crate) fn method_not_allowed() -> Self {
+    Reply::error(405, "Only `POST` method is allowed")
+  }
+
+  #[cfg(feature = "post-json-hyper")]
+  pub(crate) fn not_found(path: &str) -> Self {
+    Reply::error(404, format!("No method at {path:?}"))
+  }
+
+  #[cfg(any(feature = "post-json-actix", feature = "post-json-hyper"))]
+  pub(crate) fn payload_too_large(limit: usize) -> Self {
+    Reply::error(413, format!("Request body is larger than {limit} bytes"))
+  }
+
+  pub(crate) fn too_many_requests(retry_after: core::time::Duration) -> Self {
+    let secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+    let reply = Reply::error(429, format!("Rate limited, retry after {secs}s"));
+    #[cfg(any(
+      feature = "post-json-actix",
+      feature = "post-json-axum",
+      feature = "post-json-hyper"
+    ))]
+    let reply = Reply { retry_after: Some(secs), ..reply };
+    reply
+  }
+}
+
+/// Decode request according to `Content-Type`, call the method and encode
+/// response according to `Accept`. Responses with
+/// [`HasMethod::retry_after`] are answered with 429.
+pub(crate) async fn call_method<API, H, E>(
+  svc: &E,
+  content_type: Option<&str>,
+  accept: Option<&str>,
+  body: &[u8],
+) -> Reply
+where
+  API: HasMethod<H>,
+  API::Res: Serialize,
+  H: DeserializeOwned,
+  E: ImplsMethod<API, H>,
+{
+  let Some(req_encoding) = content_type.and_then(Encoding::from_mime) else {
+    let expected = Encoding::all().iter().map(|e| e.mime()).collect::<Vec<_>>().join(", ");
+    return Reply::error(415, format!("Expected request with `Content-Type` one of: {expected}"));
+  };
+  let Some(res_encoding) = Encoding::negotiate(accept, req_encoding) else {
+    return Reply::error(406, "No acceptable response encoding");
+  };
+  let request: H = match req_encoding.decode(body) {
+    Ok(request) => request,
+    Err(err) => return Reply::error(if err.is_data() { 422 } else { 400 }, err.to_string()),
+  };
+  let res = svc.call_api(request).await;
+  if let Some(retry_after) = API::retry_after(&res) {
+    return Reply::too_many_requests(retry_after);
+  }
+  match res_encoding.encode(&res) {
+    Ok(body) => Reply::new(200, res_encoding.mime(), body),
+    Err(err) => Reply::error(500, err.to_string()),
+  }
+}
+
+/// API method list traversal trait for calling methods by name at runtime.
+/// Used by servers that don't build a router per method.
+#[cfg(any(feature = "post-json-hyper", feature = "loopback"))]
+pub trait DispatchPostJson<API, E>: crate::MethodNames<API> {
+  /// Call method by its name, `None` if there's no such method.
+  fn dispatch(
+    svc: &E,
+    method_name: &str,
+    content_type: Option<&str>,
+    accept: Option<&str>,
+    body: &[u8],
+  ) -> impl Future<Output = Option<Reply>> + Send;
+}
+
+#[cfg(any(feature = "post-json-hyper", feature = "loopback"))]
+impl<API, E, H, T> DispatchPostJson<API, E> for (H, T)
+where
+  API: HasMethod<H>,
+  API::Res: Serialize,
+  H: DeserializeOwned + Send,
+  E: ImplsMethod<API, H> + Sync,
+  T: DispatchPostJson<API, E>,
+{
+  async fn dispatch(
+    svc: &E,
+    method_name: &str,
+    content_type: Option<&str>,
+    accept: Option<&str>,
+    body: &[u8],
+  ) -> Option<Reply> {
+    if method_name == API::METHOD_NAME {
+      Some(call_method::<API, H, E>(svc, content_type, accept, body).await)
+    } else {
+      T::dispatch(svc, method_name, content_type, accept, body).await
+    }
+  }
+}
+
+#[cfg(any(feature = "post-json-hyper", feature = "loopback"))]
+impl<API, E: Sync> DispatchPostJson<API, E> for () {
+  async fn dispatch(_: &E, _: &str, _: Option<&str>, _: Option<&str>, _: &[u8]) -> Option<Reply> {
+    None
+  }
+}
@@ -0,0 +1,297 @@
+//! Serve OpenAPI spec and docs UI from the axum router.
+
+use axum::{Router, body::Bytes, http::header, response::Html, routing::get};
+use documented::DocumentedOpt;
+
+use super::openapi::{GenerateOpenApi, gen_openapi_with};
+use super::route::PostJsonRoutes;
+use crate::IsApi;
+
+/// Docs UI page served by [`OpenApiDocs`]. The page is embedded into the
+/// binary, UI scripts are loaded from a public CDN at pinned versions, see
+/// [`UiAsset`]. With `post-json-openapi-vendored` feature the scripts are
+/// embedded into the page instead, so the docs work offline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DocsUi {
+  /// [Swagger UI](https://swagger.io/tools/swagger-ui/), default.
+  #[default]
+  SwaggerUi,
+  /// [Redoc](https://github.com/Redocly/redoc)
+  Redoc,
+  /// [Scalar](https://github.com/scalar/scalar)
+  Scalar,
+}
+
+impl DocsUi {
+  /// Default script of the UI loaded from a CDN.
+  pub fn script(self) -> UiAsset {
+    UiAsset::new(match self {
+      DocsUi::SwaggerUi => "https://unpkg.com/swagger-ui-dist@5.17.14/swagger-ui-bundle.js",
+      DocsUi::Redoc => "https://cdn.jsdelivr.net/npm/redoc@2.1.5/bundles/redoc.standalone.js",
+      DocsUi::Scalar => {
+        "https://cdn.jsdelivr.net/npm/@scalar/api-reference@1.25.0/dist/browser/standalone.js"
+      }
+    })
+  }
+
+  /// Default stylesheet of the UI loaded from a CDN, if it needs one.
+  pub fn style(self) -> Option<UiAsset> {
+    match self {
+      DocsUi::SwaggerUi => {
+        Some(UiAsset::new("https://unpkg.com/swagger-ui-dist@5.17.14/swagger-ui.css"))
+      }
+      DocsUi::Redoc | DocsUi::Scalar => None,
+    }
+  }
+}
+
+/// Script or stylesheet of the docs UI page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UiAsset {
+  url: String,
+  integrity: Option<String>,
+}
+
+impl UiAsset {
+  pub fn new(url: impl Into<String>) -> Self {
+    Self { url: url.into(), integrity: None }
+  }
+
+  /// [Subresource integrity](https://developer.mozilla.org/en-US/docs/Web/Security/Subresource_Integrity)
+  /// hash of the asset, e.g. `sha384-...`. Also makes the browser load the
+  /// asset in CORS mode.
+  pub fn integrity(self, integrity: impl Into<String>) -> Self {
+    Self { integrity: Some(integrity.into()), ..self }
+  }
+
+  fn attrs(&self, url_attr: &str) -> String {
+    let mut attrs = format!(r#"{url_attr}="{}""#, escape_html(&self.url));
+    if let Some(integrity) = &self.integrity {
+      attrs += &format!(r#" integrity="{}" crossorigin="anonymous""#, escape_html(integrity));
+    }
+    attrs
+  }
+}
+
+/// Serves OpenAPI spec generated from an API type along with a docs UI page,
+/// see [`super::server::mk_post_json_router_with_docs`].
+///
+/// By default mounts `/openapi.json`, `/openapi.yaml` (with
+/// `post-json-openapi-yaml` feature) and Swagger UI at `/docs`. The spec is
+/// generated once when the router is built.
+///
+/// The docs UI page loads its scripts and stylesheet from a public CDN, so the
+/// browser needs access to it. Enable `post-json-openapi-vendored` feature to
+/// embed them into the page, or point [`OpenApiDocs::ui_script`] and
+/// [`OpenApiDocs::ui_style`] to self-hosted copies.
+///
+/// ```ignore
+/// mk_post_json_router_with_docs::<SomeAPI, SomeBackend>(
+///   PostJsonConfig::default(),
+///   &OpenApiDocs::new().ui(DocsUi::Scalar),
+/// )
+/// .with_state(backend)
+/// ```
+#[derive(Debug, Clone)]
+pub struct OpenApiDocs {
+  json_path: String,
+  #[cfg(feature = "post-json-openapi-yaml")]
+  yaml_path: Option<String>,
+  ui_path: Option<String>,
+  ui: DocsUi,
+  ui_script: Option<UiAsset>,
+  ui_style: Option<UiAsset>,
+}
+
+impl Default for OpenApiDocs {
+  fn default() -> Self {
+    Self {
+      json_path: "/openapi.json".into(),
+      #[cfg(feature = "post-json-openapi-yaml")]
+      yaml_path: Some("/openapi.yaml".into()),
+      ui_path: Some("/docs".into()),
+      ui: DocsUi::default(),
+      ui_script: None,
+      ui_style: None,
+    }
+  }
+}
+
+impl OpenApiDocs {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Path of the JSON spec, default: `/openapi.json`.
+  pub fn json_path(self, path: impl Into<String>) -> Self {
+    Self { json_path: path.into(), ..self }
+  }
+
+  /// Path of the YAML spec, default: `/openapi.yaml`.
+  #[cfg(feature = "post-json-openapi-yaml")]
+  pub fn yaml_path(self, path: impl Into<String>) -> Self {
+    Self { yaml_path: Some(path.into()), ..self }
+  }
+
+  /// Don't serve the YAML spec.
+  #[cfg(feature = "post-json-openapi-yaml")]
+  pub fn without_yaml(self) -> Self {
+    Self { yaml_path: None, ..self }
+  }
+
+  /// Path of the docs UI page, default: `/docs`.
+  pub fn ui_path(self, path: impl Into<String>) -> Self {
+    Self { ui_path: Some(path.into()), ..self }
+  }
+
+  /// Don't serve the docs UI page.
+  pub fn without_ui(self) -> Self {
+    Self { ui_path: None, ..self }
+  }
+
+  /// Docs UI flavor, default: [`DocsUi::SwaggerUi`].
+  pub fn ui(self, ui: DocsUi) -> Self {
+    Self { ui, ..self }
+  }
+
+  /// Load the UI script from a custom location, e.g. a self-hosted copy,
+  /// instead of [`DocsUi::script`].
+  pub fn ui_script(self, script: UiAsset) -> Self {
+    Self { ui_script: Some(script), ..self }
+  }
+
+  /// Load the UI stylesheet from a custom location instead of
+  /// [`DocsUi::style`].
+  pub fn ui_style(self, style: UiAsset) -> Self {
+    Self { ui_style: Some(style), ..self }
+  }
+
+  /// Build a router serving the spec of API methods at `routes` and the docs
+  /// UI.
+  pub(crate) fn router<API, S>(&self, routes: &PostJsonRoutes) -> Router<S>
+  where
+    API::Methods: GenerateOpenApi<API>,
+    API: IsApi + DocumentedOpt,
+    S: Clone + Send + Sync + 'static,
+  {
+    let spec = gen_openapi_with::<API>(routes);
+    let json = Bytes::from(serde_json::to_vec(&spec).unwrap());
+    let mut router = Router::new().route(
+      &self.json_path,
+      get(async move || ([(header::CONTENT_TYPE, "application/json")], json)),
+    );
+    #[cfg(feature = "post-json-openapi-yaml")]
+    if let Some(yaml_path) = &self.yaml_path {
+      let yaml = Bytes::from(serde_yaml::to_string(&spec).unwrap());
+      router = router
+        .route(yaml_path, get(async move || ([(header::CONTENT_TYPE, "application/yaml")], yaml)));
+    }
+    if let Some(ui_path) = &self.ui_path {
+      let page = Html(self.ui_page(API::API_NAME));
+      router = router.route(ui_path, get(async move || page));
+    }
+    router
+  }
+}
+
+impl OpenApiDocs {
+  fn ui_page(&self, title: &str) -> String {
+    #[cfg(feature = "post-json-openapi-vendored")]
+    if self.ui_script.is_none() && self.ui_style.is_none() {
+      return vendored_ui_page(self.ui, title, &self.json_path);
+    }
+    let script = self.ui_script.clone().unwrap_or_else(|| self.ui.script());
+    let style = self.ui_style.clone().or_else(|| self.ui.style());
+    let style = style.map(|s| format!("<link rel=\"stylesheet\" {} />", s.attrs("href")));
+    let style = style.unwrap_or_default();
+    let script = script.attrs("src");
+    let title = escape_html(title);
+    let body = match self.ui {
+      DocsUi::SwaggerUi => {
+        let spec_url = escape_js(&self.json_path);
+        format!(
+          r##"<div id="swagger-ui"></div>
+    <script {script}></script>
+    <script>window.ui = SwaggerUIBundle({{ url: "{spec_url}", dom_id: "#swagger-ui" }});</script>"##
+        )
+      }
+      DocsUi::Redoc => {
+        let spec_url = escape_html(&self.json_path);
+        format!(
+          r#"<redoc spec-url="{spec_url}"></redoc>
+    <script {script}></script>"#
+        )
+      }
+      DocsUi::Scalar => {
+        let spec_url = escape_html(&self.json_path);
+        format!(
+          r#"<script id="api-reference" data-url="{spec_url}"></script>
+    <script {script}></script>"#
+        )
+      }
+    };
+    format!(
+      r#"<!doctype html>
+<html>
+  <head>
+    <meta charset="utf-8" />
+    <meta name="viewport" content="width=device-width, initial-scale=1" />
+    <title>{title}</title>
+    {style}
+  </head>
+  <body>
+    {body}
+  </body>
+</html>
+"#
+    )
+  }
+}
+
+/// Page with the UI scripts embedded by `aide`.
+#[cfg(feature = "post-json-openapi-vendored")]
+fn vendored_ui_page(ui: DocsUi, title: &str, spec_url: &str) -> String {
+  // `aide` puts the url into a JS string literal and the title into HTML
+  let (title, spec_url) = (escape_html(title), escape_js(spec_url));
+  match ui {
+    DocsUi::SwaggerUi => aide::swagger::Swagger::new(spec_url).with_title(&title).html(),
+    DocsUi::Redoc => aide::redoc::Redoc::new(spec_url).with_title(&title).html(),
+    DocsUi::Scalar => aide::scalar::Scalar::new(spec_url).with_title(&title).html(),
+  }
+}
+
+fn escape_html(s: &str) -> String {
+  s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Escape a string to be put between quotes of a JS string literal inside a
+/// `<script>` tag.
+fn escape_js(s: &str) -> String {
+  s.chars()
+    .map(|c| match c {
+      '\\' => "\\\\".into(),
+      '"' | '\'' | '<' | '>' | '&' | '\u{2028}' | '\u{2029}' => format!("\\u{:04x}", c as u32),
+      c if c.is_control() => format!("\\u{:04x}", c as u32),
+      c => c.to_string(),
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn ui_page_escaping() {
+    let docs = OpenApiDocs::new()
+      .json_path(r#"/spec"</script>.json"#)
+      .ui_script(UiAsset::new("/assets/swagger-ui.js").integrity("sha384-abc"));
+    let page = docs.ui_page("<API>");
+    assert!(page.contains("<title>&lt;API&gt;</title>"));
+    assert!(page.contains(r#"url: "/spec\u0022\u003c/script\u003e.json""#));
+    assert!(page.contains(
+      r#"<script src="/assets/swagger-ui.js" integrity="sha384-abc" crossorigin="anonymous">"#
+    ));
+    assert!(page.contains("swagger-ui-dist@5.17.14/swagger-ui.css"));
+  }
+}
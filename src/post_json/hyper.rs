@@ -0,0 +1,170 @@
+//! Make a server as HTTP `POST /<method_name>` with plain `hyper`, without
+//! `axum`.
+//!
+//! See [`PostJsonService`].
+
+use core::convert::Infallible;
+use core::marker::PhantomData;
+use core::pin::Pin;
+use http_body_util::{BodyExt, Full, LengthLimitError, Limited};
+use hyper::body::{Body, Bytes};
+use hyper::header::{ACCEPT, CONTENT_TYPE};
+#[cfg(feature = "post-json-compression")]
+use hyper::header::{ACCEPT_ENCODING, CONTENT_ENCODING};
+use hyper::{Method, Request, Response, StatusCode};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[cfg(feature = "post-json-compression")]
+use super::compression::{self, ContentCoding};
+use super::dispatch::Reply;
+use crate::{IsApi, MethodNames};
+
+pub use super::codec::{CodecError, Encoding};
+pub use super::dispatch::{DispatchPostJson, ErrorBody, PostJsonConfig};
+pub use super::route::{PostJsonRoutes, RouteCase};
+
+/// [`hyper::service::Service`] where each method is `POST /<method_name>`.
+///
+/// Has the same semantics and error responses as the axum router.
+///
+/// ```ignore
+/// let svc = PostJsonService::<SomeAPI, _>::new(SomeBackend::default());
+/// http1::Builder::new().serve_connection(TokioIo::new(stream), svc).await
+/// ```
+pub struct PostJsonService<API, E> {
+  implementor: E,
+  config: Arc<PostJsonConfig>,
+  /// Method names by their routes.
+  methods: Arc<HashMap<String, &'static str>>,
+  api_marker: PhantomData<fn() -> API>,
+}
+
+impl<API, E: Clone> Clone for PostJsonService<API, E> {
+  fn clone(&self) -> Self {
+    Self {
+      implementor: self.implementor.clone(),
+      config: self.config.clone(),
+      methods: self.methods.clone(),
+      api_marker: PhantomData,
+    }
+  }
+}
+
+impl<API, E> PostJsonService<API, E>
+where
+  API: IsApi,
+  API::Methods: MethodNames<API>,
+{
+  pub fn new(implementor: E) -> Self {
+    Self::with_config(implementor, PostJsonConfig::default())
+  }
+
+  /// Same as [`PostJsonService::new`] but with custom [`PostJsonConfig`] or
+  /// [`PostJsonRoutes`].
+  pub fn with_config(implementor: E, config: impl Into<PostJsonConfig>) -> Self {
+    let config = config.into();
+    let methods = Arc::new(config.route_table::<API>());
+    Self { implementor, config: Arc::new(config), methods, api_marker: PhantomData }
+  }
+}
+
+impl<API, E, B> hyper::service::Service<Request<B>> for PostJsonService<API, E>
+where
+  API: IsApi + 'static,
+  API::Methods: DispatchPostJson<API, E>,
+  E: Clone + Send + Sync + 'static,
+  B: Body + Send + 'static,
+  B::Data: Send,
+  B::Error: std::error::Error + Send + Sync + 'static,
+{
+  type Response = Response<Full<Bytes>>;
+  type Error = Infallible;
+  type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Infallible>> + Send>>;
+
+  fn call(&self, req: Request<B>) -> Self::Future {
+    let implementor = self.implementor.clone();
+    let config = self.config.clone();
+    let methods = self.methods.clone();
+    Box::pin(async move {
+      if req.method() != Method::POST {
+        return Ok(Reply::method_not_allowed().into());
+      }
+      let (parts, body) = req.into_parts();
+      let path = parts.uri.path();
+      let Some(&method_name) = methods.get(path) else {
+        return Ok(Reply::not_found(path).into());
+      };
+      let header = |name| parts.headers.get(name).and_then(|v| v.to_str().ok());
+      let (content_type, accept) = (header(CONTENT_TYPE), header(ACCEPT));
+      let limit = config.method_body_limit_of(method_name);
+      let body = match Limited::new(body, limit).collect().await {
+        Ok(body) => body.to_bytes(),
+        Err(err) if err.is::<LengthLimitError>() => {
+          return Ok(Reply::payload_too_large(limit).into());
+        }
+        Err(err) => {
+          return Ok(Reply::error(400, format!("Failed to read request body: {err}")).into());
+        }
+      };
+      #[cfg(feature = "post-json-compression")]
+      let body = match header(CONTENT_ENCODING).filter(|_| config.compression) {
+        Some(content_encoding) => match compression::decompress(content_encoding, &body, limit) {
+          Ok(body) => Bytes::from(body),
+          Err(err) => return Ok(decompression_error(err, limit).into()),
+        },
+        None => body,
+      };
+      let reply = API::Methods::dispatch(&implementor, method_name, content_type, accept, &body);
+      let reply = reply.await.unwrap_or_else(|| Reply::not_found(method_name));
+      #[cfg(feature = "post-json-compression")]
+      if config.compression {
+        return Ok(compress(reply, header(ACCEPT_ENCODING)));
+      }
+      Ok(reply.into())
+    })
+  }
+}
+
+#[cfg(feature = "post-json-compression")]
+fn decompression_error(err: std::io::Error, limit: usize) -> Reply {
+  match err.kind() {
+    std::io::ErrorKind::FileTooLarge => Reply::payload_too_large(limit),
+    std::io::ErrorKind::Unsupported => Reply::error(415, err.to_string()),
+    _ => Reply::error(400, format!("Failed to decompress request body: {err}")),
+  }
+}
+
+/// Compress a response according to `Accept-Encoding`, small bodies are left
+/// as they are like `tower-http` does.
+#[cfg(feature = "post-json-compression")]
+fn compress(reply: Reply, accept_encoding: Option<&str>) -> Response<Full<Bytes>> {
+  use hyper::header::{HeaderValue, VARY};
+  let coding = accept_encoding.and_then(ContentCoding::negotiate).filter(|_| reply.body.len() > 32);
+  let compressed = coding.and_then(|coding| Some((coding, coding.compress(&reply.body).ok()?)));
+  let mut res: Response<Full<Bytes>> = match compressed {
+    Some((coding, body)) => {
+      let mut res: Response<Full<Bytes>> = Reply { body, ..reply }.into();
+      res.headers_mut().insert(CONTENT_ENCODING, HeaderValue::from_static(coding.name()));
+      res
+    }
+    None => reply.into(),
+  };
+  res.headers_mut().insert(VARY, HeaderValue::from_static("accept-encoding"));
+  res
+}
+
+impl From<Reply> for Response<Full<Bytes>> {
+  fn from(reply: Reply) -> Self {
+    let mut res = Response::new(Full::new(Bytes::from(reply.body)));
+    *res.status_mut() =
+      StatusCode::from_u16(reply.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    res
+      .headers_mut()
+      .insert(CONTENT_TYPE, hyper::header::HeaderValue::from_static(reply.content_type));
+    if let Some(secs) = reply.retry_after {
+      res.headers_mut().insert(hyper::header::RETRY_AFTER, secs.into());
+    }
+    res
+  }
+}
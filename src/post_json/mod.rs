@@ -1,12 +1,48 @@
 //! HTTP `POST /<method_name>` with JSON bodies.
 
+#[cfg(feature = "post-json-actix")]
+pub mod actix;
 #[cfg(feature = "client")]
 pub mod client;
+#[cfg(feature = "post-json-hyper")]
+pub mod hyper;
 #[cfg(feature = "post-json-openapi")]
 pub mod openapi;
 #[cfg(feature = "post-json-axum")]
 pub mod server;
 
+#[cfg(any(
+  feature = "client",
+  feature = "post-json-actix",
+  feature = "post-json-axum",
+  feature = "post-json-hyper",
+  feature = "post-json-openapi",
+  feature = "loopback"
+))]
+pub(crate) mod codec;
+#[cfg(all(
+  feature = "post-json-compression",
+  any(feature = "client", feature = "post-json-hyper")
+))]
+pub(crate) mod compression;
+#[cfg(any(
+  feature = "post-json-actix",
+  feature = "post-json-axum",
+  feature = "post-json-hyper",
+  feature = "loopback"
+))]
+pub(crate) mod dispatch;
+#[cfg(all(feature = "post-json-axum", feature = "post-json-openapi"))]
+mod docs;
+#[cfg(any(
+  feature = "client",
+  feature = "post-json-actix",
+  feature = "post-json-axum",
+  feature = "post-json-hyper",
+  feature = "post-json-openapi"
+))]
+mod route;
+
 #[cfg(test)]
 #[cfg(all(feature = "client", feature = "post-json-axum"))]
 mod tests {
@@ -42,6 +78,489 @@ mod tests {
     assert_eq!(new_a, true);
     assert!(client.call_api(PostA(true)).await.unwrap().is_err());
 
+    type BoxError = Box<dyn std::error::Error + Send + Sync>;
+    let client = crate::combinator::FlattenErr::<BoxError, _>::new(client);
+    let err = client.call_api(PostA(true)).await.unwrap_err();
+    assert_eq!(err.to_string(), "can't post `a` anymore");
+
+    server_thread.abort();
+  }
+  #[tokio::test]
+  async fn axum_reqwest_routes() {
+    use super::client::PostJsonClient;
+    use super::server::{PostJsonRoutes, RouteCase};
+    use crate::ImplsMethod;
+    use crate::test::*;
+    use std::net::Ipv4Addr;
+
+    let routes = PostJsonRoutes::new().base_path("/api/v1").case(RouteCase::Kebab);
+    let router = super::server::mk_post_json_router_with::<SomeAPI, SomeBackend>(routes.clone())
+      .with_state(SomeBackend::default());
+
+    let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server_thread = tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+    let url = reqwest::Url::parse(&format!("http://{addr}/")).unwrap();
+    let res = reqwest::Client::new().post(url.join("api/v1/get-a").unwrap()).json(&GetA).send();
+    assert!(res.await.unwrap().status().is_success());
+
+    let client: PostJsonClient<SomeAPI> =
+      PostJsonClient::new(url, reqwest::Client::new()).unwrap().with_routes(routes);
+    client.call_api(PostA(true)).await.unwrap().unwrap();
+    assert!(client.call_api(GetA).await.unwrap());
+
+    server_thread.abort();
+  }
+  #[cfg(feature = "post-json-openapi-yaml")]
+  #[tokio::test]
+  async fn axum_openapi_docs() {
+    use super::server::{DocsUi, OpenApiDocs, PostJsonRoutes, mk_post_json_router_with_docs};
+    use crate::test::*;
+    use std::net::Ipv4Addr;
+
+    let routes = PostJsonRoutes::new().base_path("/api");
+    let docs = OpenApiDocs::new().ui(DocsUi::Redoc);
+    let router = mk_post_json_router_with_docs::<SomeAPI, SomeBackend>(routes, &docs)
+      .with_state(SomeBackend::default());
+
+    let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server_thread = tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+    let get = async |path: &str| {
+      let res = reqwest::get(format!("http://{addr}{path}")).await.unwrap();
+      assert!(res.status().is_success());
+      res.text().await.unwrap()
+    };
+    let json: serde_json::Value = serde_json::from_str(&get("/openapi.json").await).unwrap();
+    assert_eq!(json["info"]["title"], "SomeAPI");
+    assert!(json["paths"]["/api/get_a"].is_object());
+    let yaml: serde_yaml::Value = serde_yaml::from_str(&get("/openapi.yaml").await).unwrap();
+    assert_eq!(yaml["info"]["title"], "SomeAPI");
+    #[cfg(not(feature = "post-json-openapi-vendored"))]
+    assert!(get("/docs").await.contains(r#"<redoc spec-url="/openapi.json">"#));
+    #[cfg(feature = "post-json-openapi-vendored")]
+    assert!(get("/docs").await.contains(r#"Redoc.init("/openapi.json""#));
+
+    server_thread.abort();
+  }
+  #[tokio::test]
+  async fn axum_reqwest_encodings() {
+    use super::client::{Encoding, PostJsonClient};
+    use super::server::ErrorBody;
+    use crate::ImplsMethod;
+    use crate::test::*;
+    use std::net::Ipv4Addr;
+
+    let router = super::server::mk_post_json_router::<SomeAPI, SomeBackend>()
+      .with_state(SomeBackend::default());
+    let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server_thread = tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    let url = reqwest::Url::parse(&format!("http://{addr}/")).unwrap();
+
+    for encoding in Encoding::all() {
+      let client: PostJsonClient<SomeAPI> =
+        PostJsonClient::new(url.clone(), reqwest::Client::new()).unwrap().with_encoding(encoding);
+      let _ = client.call_api(PostA(true)).await.unwrap();
+      assert!(client.call_api(GetA).await.unwrap());
+    }
+
+    let post = |path: &str, content_type: &'static str, body: &'static str| {
+      let req = reqwest::Client::new().post(url.join(path).unwrap());
+      async move { req.header("content-type", content_type).body(body).send().await.unwrap() }
+    };
+    let res = post("get_a", "text/plain", "null").await;
+    assert_eq!(res.status(), reqwest::StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    let res = post("get_a", "application/json", "nul").await;
+    assert_eq!(res.status(), reqwest::StatusCode::BAD_REQUEST);
+    assert_eq!(res.json::<ErrorBody>().await.unwrap().code, 400);
+    let res = post("post_a", "application/json", r#""a""#).await;
+    assert_eq!(res.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+    let res = post("get_a", "application/vnd.some-api+json", "null").await;
+    assert!(res.status().is_success());
+
+    server_thread.abort();
+  }
+  #[tokio::test]
+  async fn axum_reqwest_body_limit() {
+    use super::client::{ClientError, PostJsonClient};
+    use super::server::{ErrorBody, PostJsonConfig, mk_post_json_router_with};
+    use crate::ImplsMethod;
+    use crate::test::*;
+    use std::net::Ipv4Addr;
+
+    let config = PostJsonConfig::new().body_limit(4).method_body_limit("get_a", 3);
+    let router =
+      mk_post_json_router_with::<SomeAPI, SomeBackend>(config).with_state(SomeBackend::default());
+    let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server_thread = tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    let url = reqwest::Url::parse(&format!("http://{addr}/")).unwrap();
+
+    let client: PostJsonClient<SomeAPI> =
+      PostJsonClient::new(url.clone(), reqwest::Client::new()).unwrap();
+    client.call_api(PostA(true)).await.unwrap().unwrap();
+    assert!(client.call_api(PostA(false)).await.is_err());
+    let Err(ClientError::Status { status, body }) = client.call_api(GetA).await else { panic!() };
+    assert_eq!(status, reqwest::StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(body.unwrap().code, 413);
+
+    let req = reqwest::Client::new().post(url.join("get_a").unwrap()).json(&GetA);
+    let res = req.send().await.unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(res.json::<ErrorBody>().await.unwrap().code, 413);
+
+    server_thread.abort();
+  }
+
+  #[cfg(feature = "tokio")]
+  #[tokio::test]
+  async fn axum_reqwest_rate_limit() {
+    use super::client::{ClientError, PostJsonClient};
+    use super::server::mk_post_json_router;
+    use crate::ImplsMethod;
+    use crate::combinator::{Backoff, Quota, RateLimit, RateLimitConfig, WithRateLimit};
+    use crate::combinator::{Retry, RetryPolicy};
+    use crate::func::FnImpl;
+    use crate::test::*;
+    use core::time::Duration;
+    use std::net::Ipv4Addr;
+
+    struct PostAPI;
+    crate::define_api! { PostAPI => {
+      "post_a", PostA => Res<()>;
+    } }
+
+    let backend = FnImpl::<PostAPI>::builder().on(|PostA(_)| async { Ok(()) }).build();
+    let config = RateLimitConfig::new().limit(Quota::per_minute(1));
+    let router = mk_post_json_router::<WithRateLimit<PostAPI>, _>()
+      .with_state(RateLimit::new(config, backend));
+    let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server_thread = tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    let url = reqwest::Url::parse(&format!("http://{addr}/")).unwrap();
+
+    let client: PostJsonClient<WithRateLimit<PostAPI>> =
+      PostJsonClient::new(url.clone(), reqwest::Client::new()).unwrap();
+    client.call_api(PostA(true)).await.unwrap().unwrap();
+    let Err(ClientError::Status { status, body }) = client.call_api(PostA(true)).await else {
+      panic!()
+    };
+    assert_eq!(status, reqwest::StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(body.unwrap().code, 429);
+
+    let req = reqwest::Client::new().post(url.join("post_a").unwrap()).json(&PostA(true));
+    let res = req.send().await.unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::TOO_MANY_REQUESTS);
+    let retry_after = res.headers()[reqwest::header::RETRY_AFTER].to_str().unwrap();
+    assert!(matches!(retry_after.parse::<u64>(), Ok(1..=60)));
+
     server_thread.abort();
+
+    let backend = FnImpl::<PostAPI>::builder().on(|PostA(_)| async { Ok(()) }).build();
+    let config = RateLimitConfig::new().limit(Quota::per(Duration::from_millis(50), 1));
+    let backoff =
+      Backoff { initial: Duration::from_millis(100), jitter: false, ..Default::default() };
+    let policy = RetryPolicy::new().backoff(backoff).method("post_a");
+    let router = mk_post_json_router::<WithRateLimit<PostAPI>, _>()
+      .with_state(Retry(policy, RateLimit::new(config, backend)));
+    let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server_thread = tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    let url = reqwest::Url::parse(&format!("http://{addr}/")).unwrap();
+
+    for _ in 0..2 {
+      let req = reqwest::Client::new().post(url.join("post_a").unwrap()).json(&PostA(true));
+      let res = req.send().await.unwrap();
+      assert_eq!(res.status(), reqwest::StatusCode::OK);
+      assert!(res.headers().get(reqwest::header::RETRY_AFTER).is_none());
+    }
+
+    server_thread.abort();
+  }
+
+  #[tokio::test]
+  async fn axum_reqwest_hooks() {
+    use super::client::{CallOptions, ClientError, HttpHooks, PostJsonClient, TokenProvider};
+    use crate::ImplsMethod;
+    use crate::test::*;
+    use axum::http::{Request, StatusCode};
+    use axum::{body::Body, middleware::Next, response::Response};
+    use core::{future::Future, pin::Pin, time::Duration};
+    use reqwest::header::{HeaderName, HeaderValue};
+    use std::net::Ipv4Addr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    async fn check(req: Request<Body>, next: Next) -> Result<Response, StatusCode> {
+      let headers = req.headers().clone();
+      let header = |name| headers.get(name).and_then(|v| v.to_str().ok());
+      if header("authorization") != Some("Bearer fresh") {
+        return Err(StatusCode::UNAUTHORIZED);
+      }
+      if header("x-client") != Some("test") || header("x-method").is_none() {
+        return Err(StatusCode::BAD_REQUEST);
+      }
+      if header("x-slow").is_some() {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+      }
+      Ok(next.run(req).await)
+    }
+
+    #[derive(Default)]
+    struct Tokens(AtomicUsize);
+    impl TokenProvider for Tokens {
+      fn token(
+        &self,
+        refresh: bool,
+      ) -> Pin<Box<dyn Future<Output = Result<String, ClientError>> + Send + '_>> {
+        Box::pin(async move {
+          if refresh {
+            self.0.fetch_add(1, Ordering::SeqCst);
+          }
+          Ok(if self.0.load(Ordering::SeqCst) > 0 { "fresh" } else { "stale" }.into())
+        })
+      }
+    }
+
+    let router = super::server::mk_post_json_router::<SomeAPI, SomeBackend>()
+      .layer(axum::middleware::from_fn(check))
+      .with_state(SomeBackend::default());
+    let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server_thread = tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    let url = reqwest::Url::parse(&format!("http://{addr}/")).unwrap();
+
+    let hooks = HttpHooks::new()
+      .header(HeaderName::from_static("x-client"), HeaderValue::from_static("test"))
+      .token_provider(Tokens::default())
+      .on_request(|method_name, headers| {
+        headers.insert("x-method", HeaderValue::from_static(method_name));
+      });
+    let client: PostJsonClient<SomeAPI> =
+      PostJsonClient::new(url, reqwest::Client::new()).unwrap().with_hooks(hooks);
+    client.call_api(PostA(true)).await.unwrap().unwrap();
+    assert!(client.call_api(GetA).await.unwrap());
+
+    let options = CallOptions::new()
+      .header(HeaderName::from_static("x-slow"), HeaderValue::from_static("1"))
+      .timeout(Duration::from_millis(50));
+    let res = client.with_options(options).call_api(GetA).await;
+    assert!(matches!(res, Err(ClientError::Timeout)));
+
+    server_thread.abort();
+  }
+  #[cfg(feature = "client-hyper")]
+  #[tokio::test]
+  async fn axum_hyper_backends() {
+    use super::client::PostJsonClient;
+    use crate::ImplsMethod;
+    use crate::client::HyperBackend;
+    use crate::test::*;
+    use std::net::Ipv4Addr;
+
+    let router = super::server::mk_post_json_router::<SomeAPI, SomeBackend>()
+      .with_state(SomeBackend::default());
+    let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server_thread = tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    let url = reqwest::Url::parse(&format!("http://{addr}/")).unwrap();
+
+    let client: PostJsonClient<SomeAPI, _> =
+      PostJsonClient::with_backend(url.clone(), HyperBackend::default()).unwrap();
+    client.call_api(PostA(true)).await.unwrap().unwrap();
+    assert!(client.call_api(GetA).await.unwrap());
+
+    #[cfg(feature = "tower")]
+    {
+      use crate::client::TowerBackend;
+      let HyperBackend(hyper_client) = HyperBackend::default();
+      let client: PostJsonClient<SomeAPI, _> =
+        PostJsonClient::with_backend(url, TowerBackend(hyper_client)).unwrap();
+      assert!(client.call_api(PostA(true)).await.unwrap().is_err());
+    }
+
+    server_thread.abort();
+  }
+  #[cfg(feature = "post-json-hyper")]
+  #[tokio::test]
+  async fn hyper_reqwest() {
+    use super::client::PostJsonClient;
+    use super::hyper::{ErrorBody, PostJsonConfig, PostJsonService};
+    use crate::ImplsMethod;
+    use crate::test::*;
+    use hyper_util::rt::TokioIo;
+    use std::net::Ipv4Addr;
+
+    let config = PostJsonConfig::new().method_body_limit("get_a", 3);
+    let svc = PostJsonService::<SomeAPI, _>::with_config(SomeBackend::default(), config);
+    let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server_thread = tokio::spawn(async move {
+      loop {
+        let (stream, _) = listener.accept().await.unwrap();
+        let conn = hyper::server::conn::http1::Builder::new()
+          .serve_connection(TokioIo::new(stream), svc.clone());
+        tokio::spawn(conn);
+      }
+    });
+    let url = reqwest::Url::parse(&format!("http://{addr}/")).unwrap();
+
+    let client: PostJsonClient<SomeAPI> =
+      PostJsonClient::new(url.clone(), reqwest::Client::new()).unwrap();
+    client.call_api(PostA(true)).await.unwrap().unwrap();
+    assert!(client.call_api(PostA(true)).await.unwrap().is_err());
+    assert!(client.call_api(GetA).await.is_err());
+
+    let res = reqwest::Client::new().post(url.join("get_a").unwrap()).json(&GetA).send().await;
+    assert_eq!(res.unwrap().json::<ErrorBody>().await.unwrap().code, 413);
+    let res = reqwest::Client::new().post(url.join("nope").unwrap()).json(&GetA).send().await;
+    assert_eq!(res.unwrap().status(), reqwest::StatusCode::NOT_FOUND);
+    let res = reqwest::get(url.join("post_a").unwrap()).await.unwrap();
+    assert_eq!(res.status(), reqwest::StatusCode::METHOD_NOT_ALLOWED);
+
+    server_thread.abort();
+  }
+
+  #[cfg(feature = "post-json-actix")]
+  #[actix_web::test]
+  async fn actix() {
+    use super::actix::{ErrorBody, PostJsonConfig, PostJsonRoutes, mk_post_json_scope};
+    use crate::test::*;
+    use actix_web::{App, http::StatusCode, test, web::Data};
+
+    let config = PostJsonConfig::new()
+      .routes(PostJsonRoutes::new().base_path("/v1"))
+      .method_body_limit("get_a", 3);
+    let app = App::new()
+      .app_data(Data::new(SomeBackend::default()))
+      .service(mk_post_json_scope::<SomeAPI, SomeBackend>("/api", config));
+    let app = test::init_service(app).await;
+
+    let req = test::TestRequest::post().uri("/api/v1/post_a").set_json(PostA(true));
+    let res: Result<(), ()> = test::call_and_read_body_json(&app, req.to_request()).await;
+    assert_eq!(res, Ok(()));
+    let req = test::TestRequest::post().uri("/api/v1/get_a").set_json(GetA);
+    let res = test::call_service(&app, req.to_request()).await;
+    assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(test::read_body_json::<ErrorBody, _>(res).await.code, 413);
+    let req =
+      test::TestRequest::post().uri("/api/v1/post_a").insert_header(("content-type", "text/plain"));
+    let res = test::call_service(&app, req.to_request()).await;
+    assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    let req = test::TestRequest::get().uri("/api/v1/post_a");
+    let res = test::call_service(&app, req.to_request()).await;
+    assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+  }
+
+  #[cfg(feature = "post-json-compression")]
+  mod big {
+    use crate::{ImplsMethod, define_api};
+    use serde::{Deserialize, Serialize};
+
+    pub struct BigAPI;
+    define_api! { BigAPI => {
+      "get_big", GetBig => String;
+    } }
+
+    #[derive(Serialize, Deserialize)]
+    pub struct GetBig(pub usize);
+
+    #[derive(Clone)]
+    pub struct BigBackend;
+    impl ImplsMethod<BigAPI, GetBig> for BigBackend {
+      async fn call_api(&self, GetBig(len): GetBig) -> String {
+        "a".repeat(len)
+      }
+    }
+  }
+
+  #[cfg(feature = "post-json-compression")]
+  #[tokio::test]
+  async fn axum_reqwest_compression() {
+    use super::client::{ContentCoding, PostJsonClient};
+    use super::server::{PostJsonConfig, mk_post_json_router_with};
+    use crate::ImplsMethod;
+    use big::*;
+    use std::net::Ipv4Addr;
+
+    let router =
+      mk_post_json_router_with::<BigAPI, BigBackend>(PostJsonConfig::new().compression(true))
+        .with_state(BigBackend);
+    let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server_thread = tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    let url = reqwest::Url::parse(&format!("http://{addr}/")).unwrap();
+
+    for encoding in ["gzip", "zstd", "br"] {
+      let req = reqwest::Client::new().post(url.join("get_big").unwrap()).json(&GetBig(10_000));
+      let res = req.header("accept-encoding", encoding).send().await.unwrap();
+      assert_eq!(res.headers()["content-encoding"], encoding);
+      assert!(res.bytes().await.unwrap().len() < 1_000);
+    }
+
+    let client: PostJsonClient<BigAPI> = PostJsonClient::new(url, reqwest::Client::new()).unwrap();
+    assert_eq!(client.call_api(GetBig(10_000)).await.unwrap().len(), 10_000);
+    for coding in [ContentCoding::Gzip, ContentCoding::Zstd, ContentCoding::Brotli] {
+      let client = client.clone().with_compression(coding);
+      assert_eq!(client.call_api(GetBig(10_000)).await.unwrap().len(), 10_000);
+    }
+
+    server_thread.abort();
+  }
+
+  #[cfg(all(feature = "post-json-compression", feature = "post-json-hyper"))]
+  #[tokio::test]
+  async fn hyper_reqwest_compression() {
+    use super::client::{ContentCoding, PostJsonClient};
+    use super::hyper::{PostJsonConfig, PostJsonService};
+    use crate::ImplsMethod;
+    use big::*;
+    use hyper_util::rt::TokioIo;
+    use std::net::Ipv4Addr;
+
+    let config = PostJsonConfig::new().compression(true).method_body_limit("get_big", 100);
+    let svc = PostJsonService::<BigAPI, _>::with_config(BigBackend, config);
+    let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server_thread = tokio::spawn(async move {
+      loop {
+        let (stream, _) = listener.accept().await.unwrap();
+        let conn = hyper::server::conn::http1::Builder::new()
+          .serve_connection(TokioIo::new(stream), svc.clone());
+        tokio::spawn(conn);
+      }
+    });
+    let url = reqwest::Url::parse(&format!("http://{addr}/")).unwrap();
+
+    for encoding in ["gzip", "zstd", "br"] {
+      let req = reqwest::Client::new().post(url.join("get_big").unwrap()).json(&GetBig(10_000));
+      let res = req.header("accept-encoding", encoding).send().await.unwrap();
+      assert_eq!(res.headers()["content-encoding"], encoding);
+      assert!(res.bytes().await.unwrap().len() < 1_000);
+    }
+
+    let client: PostJsonClient<BigAPI> = PostJsonClient::new(url.clone(), reqwest::Client::new())
+      .unwrap()
+      .with_compression(ContentCoding::Gzip);
+    assert_eq!(client.call_api(GetBig(10_000)).await.unwrap().len(), 10_000);
+
+    let body = ContentCoding::Gzip.compress(&[b' '; 200]).unwrap();
+    let req = reqwest::Client::new().post(url.join("get_big").unwrap()).body(body);
+    let req = req.header("content-type", "application/json").header("content-encoding", "gzip");
+    assert_eq!(req.send().await.unwrap().status(), reqwest::StatusCode::PAYLOAD_TOO_LARGE);
+
+    server_thread.abort();
+  }
+
+  #[test]
+  #[should_panic(expected = "body limit is set for `get_c` which is not a method of `SomeAPI`")]
+  fn unknown_method_body_limit() {
+    use super::server::{PostJsonConfig, mk_post_json_router_with};
+    use crate::test::*;
+
+    let config = PostJsonConfig::new().method_body_limit("get_c", 3);
+    let _ = mk_post_json_router_with::<SomeAPI, SomeBackend>(config);
   }
 }
@@ -1,8 +1,10 @@
 //! OpenAPI spec generator for an API.
 //!
-//! Generates method definitions as `POST /<method_name>`
+//! Generates method definitions as `POST /<method_name>`, request and response
+//! bodies are listed for each [`Encoding`] enabled by crate features.
 //!
-//! Use [`gen_openapi`] or [`gen_openapi_yaml`].
+//! Use [`gen_openapi`] or [`gen_openapi_yaml`], or their `_with` variants for
+//! custom [`PostJsonRoutes`].
 
 use aide::openapi::*;
 use documented::DocumentedOpt;
@@ -10,17 +12,21 @@ use indexmap::IndexMap;
 use schemars::{JsonSchema, SchemaGenerator, generate::SchemaSettings};
 
 use crate::generate::split_docs;
-use crate::{HasMethod, IsApi};
+use crate::{HasMethod, IsApi, MethodNames};
+
+pub use super::codec::{CodecError, Encoding};
+pub use super::route::{PostJsonRoutes, RouteCase};
 
 /// API methods traversal trait for collecting methods and inserting request and
 /// response schemas and their dependencies schema in [`SchemaGenerator`].
 ///
 /// Don't use this trait directly, use [`gen_openapi`] or [`gen_openapi_yaml`]
 /// instead.
-pub trait GenerateOpenApi<API> {
+pub trait GenerateOpenApi<API>: MethodNames<API> {
   fn generate_openapi(
     paths: &mut IndexMap<String, ReferenceOr<PathItem>>,
     generator: &mut SchemaGenerator,
+    routes: &PostJsonRoutes,
   );
 }
 
@@ -34,46 +40,27 @@ where
   fn generate_openapi(
     paths: &mut IndexMap<String, ReferenceOr<PathItem>>,
     generator: &mut SchemaGenerator,
+    routes: &PostJsonRoutes,
   ) {
     let req_schema = T::json_schema(generator);
     let res_schema = Res::json_schema(generator);
 
     let (summary, description) = split_docs(<API as HasMethod<T>>::METHOD_DOCS);
     paths.insert(
-      format!("/{}", API::METHOD_NAME),
+      routes.path(API::METHOD_NAME),
       ReferenceOr::Item(PathItem {
         post: Some(Operation {
           summary,
           description,
           request_body: Some(ReferenceOr::Item(RequestBody {
             required: true,
-            content: IndexMap::from_iter([(
-              "application/json".into(),
-              MediaType {
-                schema: Some(SchemaObject {
-                  json_schema: req_schema,
-                  example: None,
-                  external_docs: None,
-                }),
-                ..Default::default()
-              },
-            )]),
+            content: content(req_schema),
             ..RequestBody::default()
           })),
           responses: Some(Responses {
             default: Some(ReferenceOr::Item(Response {
               description: "Successful response".into(),
-              content: IndexMap::from_iter([(
-                "application/json".into(),
-                MediaType {
-                  schema: Some(SchemaObject {
-                    json_schema: res_schema,
-                    example: None,
-                    external_docs: None,
-                  }),
-                  ..Default::default()
-                },
-              )]),
+              content: content(res_schema),
               ..Default::default()
             })),
             ..Default::default()
@@ -84,14 +71,27 @@ where
       }),
     );
 
-    N::generate_openapi(paths, generator);
+    N::generate_openapi(paths, generator, routes);
   }
 }
 
+/// Same schema for each supported [`Encoding`].
+fn content(json_schema: schemars::Schema) -> IndexMap<String, MediaType> {
+  Encoding::all()
+    .into_iter()
+    .map(|encoding| {
+      let schema =
+        SchemaObject { json_schema: json_schema.clone(), example: None, external_docs: None };
+      (encoding.mime().into(), MediaType { schema: Some(schema), ..Default::default() })
+    })
+    .collect()
+}
+
 impl<API> GenerateOpenApi<API> for () {
   fn generate_openapi(
     _paths: &mut IndexMap<String, ReferenceOr<PathItem>>,
     _gen: &mut SchemaGenerator,
+    _routes: &PostJsonRoutes,
   ) {
   }
 }
@@ -102,6 +102,15 @@ impl<API> GenerateOpenApi<API> for () {
 /// gen_openapi<SomeAPI>
 /// ```
 pub fn gen_openapi<API>() -> OpenApi
+where
+  API::Methods: GenerateOpenApi<API>,
+  API: IsApi + DocumentedOpt,
+{
+  gen_openapi_with::<API>(&PostJsonRoutes::default())
+}
+
+/// Same as [`gen_openapi`] but with custom [`PostJsonRoutes`].
+pub fn gen_openapi_with<API>(routes: &PostJsonRoutes) -> OpenApi
 where
   API::Methods: GenerateOpenApi<API>,
   API: IsApi + DocumentedOpt,
@@ -109,8 +118,9 @@ where
   let mut generator = SchemaGenerator::new(SchemaSettings::openapi3().with(|s| {
     s.definitions_path = "#/components/schemas/".into();
   }));
+  routes.table::<API>();
   let mut paths = IndexMap::new();
-  API::Methods::generate_openapi(&mut paths, &mut generator);
+  API::Methods::generate_openapi(&mut paths, &mut generator, routes);
   let (summary, description) = split_docs(API::DOCS);
   OpenApi {
     info: Info {
@@ -150,13 +160,34 @@ where
   serde_yaml::to_string(&gen_openapi::<API>()).unwrap()
 }
 
+/// Same as [`gen_openapi_yaml`] but with custom [`PostJsonRoutes`].
+#[cfg(feature = "post-json-openapi-yaml")]
+pub fn gen_openapi_yaml_with<API>(routes: &PostJsonRoutes) -> String
+where
+  API::Methods: GenerateOpenApi<API>,
+  API: IsApi + DocumentedOpt,
+{
+  serde_yaml::to_string(&gen_openapi_with::<API>(routes)).unwrap()
+}
+
 #[cfg(feature = "post-json-openapi-yaml")]
 #[test]
 fn test_openapi() {
   use crate::test::SomeAPI;
   use serde_yaml::Value;
 
-  let spec = serde_yaml::to_value(gen_openapi::<SomeAPI>()).unwrap();
+  let mut spec = serde_yaml::to_value(gen_openapi::<SomeAPI>()).unwrap();
+  // alternative encodings must share the JSON schema
+  for (_, path) in spec["paths"].as_mapping_mut().unwrap() {
+    for content in ["/post/requestBody/content", "/post/responses/default/content"] {
+      let content = content.split('/').skip(1).fold(&mut *path, |v, k| &mut v[k]);
+      let content = content.as_mapping_mut().unwrap();
+      for encoding in Encoding::all().into_iter().skip(1) {
+        let media = content.remove(encoding.mime()).unwrap();
+        assert_eq!(media, content["application/json"]);
+      }
+    }
+  }
   let spec_ref: Value = serde_yaml::from_str(
     r#"
     openapi: 3.1.0
@@ -215,3 +246,14 @@ fn test_openapi() {
   .unwrap();
   assert_eq!(spec, spec_ref);
 }
+
+#[cfg(feature = "post-json-openapi-yaml")]
+#[test]
+fn test_openapi_routes() {
+  use crate::test::SomeAPI;
+
+  let routes = PostJsonRoutes::new().base_path("/api/v1").case(RouteCase::Kebab);
+  let spec = gen_openapi_with::<SomeAPI>(&routes);
+  let paths: Vec<_> = spec.paths.unwrap().paths.into_keys().collect();
+  assert_eq!(paths, ["/api/v1/get-a", "/api/v1/post-a"]);
+}
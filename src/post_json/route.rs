@@ -0,0 +1,205 @@
+//! Route naming strategy for HTTP `POST /<method_name>`.
+
+use core::fmt;
+use std::sync::Arc;
+
+/// Case conversion applied to method names when building routes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RouteCase {
+  /// Keep method names as they are, default.
+  #[default]
+  AsIs,
+  /// `snake_case`
+  Snake,
+  /// `kebab-case`
+  Kebab,
+  /// `camelCase`
+  Camel,
+}
+
+/// Strategy for mapping method names to HTTP routes.
+///
+/// The same strategy must be used by the server router, the client and the
+/// OpenAPI generator. By default each method is mapped to `/<method_name>`.
+///
+/// Two methods mapped to the same route are reported with a panic when a
+/// server router is built.
+///
+/// ```
+/// # #[cfg(feature = "post-json-axum")] {
+/// # use aisil::server::post_json::{PostJsonRoutes, RouteCase};
+/// let routes = PostJsonRoutes::new().base_path("/api/v1").case(RouteCase::Kebab).nest_dotted(true);
+/// assert_eq!(routes.path("user.get_name"), "/api/v1/user/get-name");
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct PostJsonRoutes {
+  base_path: String,
+  case: RouteCase,
+  nest_dotted: bool,
+  map: Option<MapFn>,
+}
+
+type MapFn = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+impl fmt::Debug for PostJsonRoutes {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("PostJsonRoutes")
+      .field("base_path", &self.base_path)
+      .field("case", &self.case)
+      .field("nest_dotted", &self.nest_dotted)
+      .field("map", &self.map.as_ref().map(|_| ".."))
+      .finish()
+  }
+}
+
+impl PostJsonRoutes {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Prefix all routes with a base path, e.g. `/api/v1`.
+  pub fn base_path(mut self, base_path: impl Into<String>) -> Self {
+    self.base_path = base_path.into();
+    self
+  }
+
+  /// Convert the case of method names, default: [`RouteCase::AsIs`].
+  pub fn case(mut self, case: RouteCase) -> Self {
+    self.case = case;
+    self
+  }
+
+  /// Map dotted method names like `user.get` to nested routes like
+  /// `/user/get`, default: `false`.
+  pub fn nest_dotted(mut self, nest_dotted: bool) -> Self {
+    self.nest_dotted = nest_dotted;
+    self
+  }
+
+  /// Custom mapping from a method name to a route relative to the base path.
+  /// Overrides case conversion and nesting.
+  pub fn map(mut self, f: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+    self.map = Some(Arc::new(f));
+    self
+  }
+
+  /// Absolute route of a method, always starts with `/`.
+  pub fn path(&self, method_name: &str) -> String {
+    let route = match &self.map {
+      Some(map) => map(method_name),
+      None if self.nest_dotted => {
+        method_name.split('.').map(|s| convert_case(s, self.case)).collect::<Vec<_>>().join("/")
+      }
+      None => convert_case(method_name, self.case),
+    };
+    let base_path = self.base_path.trim_matches('/');
+    let route = route.trim_start_matches('/');
+    if base_path.is_empty() { format!("/{route}") } else { format!("/{base_path}/{route}") }
+  }
+}
+
+#[cfg(any(
+  feature = "post-json-actix",
+  feature = "post-json-axum",
+  feature = "post-json-hyper",
+  feature = "post-json-openapi"
+))]
+impl PostJsonRoutes {
+  /// Routes of all methods of an API.
+  ///
+  /// # Panics
+  ///
+  /// When two methods are mapped to the same route.
+  pub(crate) fn table<API>(&self) -> std::collections::HashMap<String, &'static str>
+  where
+    API: crate::IsApi,
+    API::Methods: crate::MethodNames<API>,
+  {
+    let mut table = std::collections::HashMap::new();
+    for name in <API::Methods as crate::MethodNames<API>>::method_names() {
+      if let Some(other) = table.insert(self.path(name), name) {
+        panic!(
+          "methods `{other}` and `{name}` of `{}` are both routed to `{}`",
+          API::API_NAME,
+          self.path(name)
+        );
+      }
+    }
+    table
+  }
+}
+
+fn convert_case(name: &str, case: RouteCase) -> String {
+  let words = || split_words(name).into_iter().map(str::to_lowercase);
+  match case {
+    RouteCase::AsIs => name.to_owned(),
+    RouteCase::Snake => words().collect::<Vec<_>>().join("_"),
+    RouteCase::Kebab => words().collect::<Vec<_>>().join("-"),
+    RouteCase::Camel => words()
+      .enumerate()
+      .map(|(i, w)| match (i, w.chars().next()) {
+        (0, _) | (_, None) => w,
+        (_, Some(c)) => c.to_uppercase().chain(w.chars().skip(1)).collect(),
+      })
+      .collect(),
+  }
+}
+
+/// Split identifier on `_`, `-`, `.`, spaces and `camelCase` boundaries.
+fn split_words(name: &str) -> Vec<&str> {
+  let mut words = Vec::new();
+  let mut start = 0;
+  let mut prev_lower = false;
+  for (i, c) in name.char_indices() {
+    if matches!(c, '_' | '-' | '.' | ' ') {
+      words.push(&name[start..i]);
+      start = i + c.len_utf8();
+      prev_lower = false;
+      continue;
+    }
+    if c.is_uppercase() && prev_lower {
+      words.push(&name[start..i]);
+      start = i;
+    }
+    prev_lower = c.is_lowercase() || c.is_ascii_digit();
+  }
+  words.push(&name[start..]);
+  words.retain(|w| !w.is_empty());
+  words
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn paths() {
+    assert_eq!(PostJsonRoutes::new().path("get_a"), "/get_a");
+    assert_eq!(PostJsonRoutes::new().base_path("api/v1/").path("get_a"), "/api/v1/get_a");
+    assert_eq!(PostJsonRoutes::new().case(RouteCase::Kebab).path("getUserName"), "/get-user-name");
+    assert_eq!(
+      PostJsonRoutes::new().case(RouteCase::Snake).path("get-user-name"),
+      "/get_user_name"
+    );
+    assert_eq!(PostJsonRoutes::new().case(RouteCase::Camel).path("get_user_name"), "/getUserName");
+    assert_eq!(PostJsonRoutes::new().nest_dotted(true).path("user.get_name"), "/user/get_name");
+    assert_eq!(PostJsonRoutes::new().path("user.get_name"), "/user.get_name");
+    assert_eq!(
+      PostJsonRoutes::new().base_path("/rpc").map(|m| format!("/m/{}", m.len())).path("get_a"),
+      "/rpc/m/5"
+    );
+  }
+
+  #[test]
+  #[cfg(any(
+    feature = "post-json-actix",
+    feature = "post-json-axum",
+    feature = "post-json-hyper",
+    feature = "post-json-openapi"
+  ))]
+  #[should_panic(expected = "methods `get_a` and `post_a` of `SomeAPI` are both routed to `/a`")]
+  fn colliding_paths() {
+    PostJsonRoutes::new().map(|m| m[m.len() - 1..].into()).table::<crate::test::SomeAPI>();
+  }
+}
@@ -1,25 +1,83 @@
 //! Make a server as HTTP `POST /<method_name>` with JSON bodies
 //!
-//! See [`mk_post_json_router`]
+//! See [`mk_post_json_router`]. With `post-json-openapi` feature the spec
+//! and a docs UI can be served too, see `mk_post_json_router_with_docs`.
 
-use crate::{HasMethod, ImplsMethod, IsApi};
-use axum::{Router, extract::Json, extract::State, routing::post};
+use super::dispatch::{Reply, call_method};
+use crate::{HasMethod, ImplsMethod, IsApi, MethodNames};
+use axum::body::Bytes;
+use axum::extract::{DefaultBodyLimit, State, rejection::BytesRejection};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::{Router, routing::post};
 use serde::{Serialize, de::DeserializeOwned};
 
+pub use super::codec::{CodecError, Encoding};
+pub use super::dispatch::{ErrorBody, PostJsonConfig};
+#[cfg(feature = "post-json-openapi")]
+pub use super::docs::*;
+pub use super::route::{PostJsonRoutes, RouteCase};
+
 /// Builds axum router where each method is `POST /<method_name>`, the request
 /// body is expected to be a json and the result is also returned as json.
+///
+/// Other [`Encoding`]s enabled by crate features are negotiated with
+/// `Content-Type` and `Accept` headers. Responses use the request encoding
+/// unless `Accept` says otherwise. Malformed requests are answered with a
+/// 4xx status and a JSON [`ErrorBody`].
 pub fn mk_post_json_router<API: crate::IsApi, S>() -> Router<S>
 where
   API::Methods: MkPostJsonRouter<API, S>,
+  S: Clone + Send + Sync + 'static,
+{
+  mk_post_json_router_with::<API, S>(PostJsonConfig::default())
+}
+
+/// Same as [`mk_post_json_router`] but with custom [`PostJsonConfig`] or
+/// [`PostJsonRoutes`].
+pub fn mk_post_json_router_with<API: crate::IsApi, S>(
+  config: impl Into<PostJsonConfig>,
+) -> Router<S>
+where
+  API::Methods: MkPostJsonRouter<API, S>,
+  S: Clone + Send + Sync + 'static,
+{
+  let config = config.into();
+  config.route_table::<API>();
+  let mut router = API::Methods::router(&config);
+  if let Some(limit) = config.body_limit {
+    router = router.layer(DefaultBodyLimit::max(limit));
+  }
+  #[cfg(feature = "post-json-compression")]
+  if config.compression {
+    use tower_http::{compression::CompressionLayer, decompression::RequestDecompressionLayer};
+    router = router.layer(CompressionLayer::new()).layer(RequestDecompressionLayer::new());
+  }
+  router
+}
+
+/// Same as [`mk_post_json_router_with`] but also serves the OpenAPI spec of
+/// the API at the configured routes and a docs UI, see [`OpenApiDocs`].
+#[cfg(feature = "post-json-openapi")]
+pub fn mk_post_json_router_with_docs<API, S>(
+  config: impl Into<PostJsonConfig>,
+  docs: &OpenApiDocs,
+) -> Router<S>
+where
+  API: IsApi + documented::DocumentedOpt,
+  API::Methods: MkPostJsonRouter<API, S> + super::openapi::GenerateOpenApi<API>,
+  S: Clone + Send + Sync + 'static,
 {
-  API::Methods::router()
+  let config = config.into();
+  let docs = docs.router::<API, S>(&config.routes);
+  mk_post_json_router_with::<API, S>(config).merge(docs)
 }
 
 /// API method list traversal trait for building axum router for each method.
 ///
 /// Use [`mk_post_json_router`].
-pub trait MkPostJsonRouter<API, E> {
-  fn router() -> Router<E>;
+pub trait MkPostJsonRouter<API, E>: MethodNames<API> {
+  fn router(config: &PostJsonConfig) -> Router<E>;
 }
 
 impl<
@@ -30,18 +88,40 @@ impl<
   T: MkPostJsonRouter<API, E>,
 > MkPostJsonRouter<API, E> for (H, T)
 {
-  fn router() -> Router<E> {
-    T::router().route(
-      &format!("/{}", API::METHOD_NAME),
-      post(|State(svc): State<E>, Json(request): Json<H>| async move {
-        Json(svc.call_api(request).await)
-      }),
-    )
+  fn router(config: &PostJsonConfig) -> Router<E> {
+    let mut handler = post(
+      |State(svc): State<E>, headers: HeaderMap, body: Result<Bytes, BytesRejection>| async move {
+        let reply = match body {
+          Ok(body) => {
+            let header = |name| headers.get(name).and_then(|v| v.to_str().ok());
+            let (content_type, accept) = (header(header::CONTENT_TYPE), header(header::ACCEPT));
+            call_method::<API, H, E>(&svc, content_type, accept, &body).await
+          }
+          Err(err) => Reply::error(err.status().as_u16(), err.body_text()),
+        };
+        reply.into_response()
+      },
+    );
+    if let Some(limit) = config.method_body_limits.get(API::METHOD_NAME) {
+      handler = handler.layer(DefaultBodyLimit::max(*limit));
+    }
+    T::router(config).route(&config.routes.path(API::METHOD_NAME), handler)
   }
 }
 
 impl<API, E: Clone + Send + Sync + 'static> MkPostJsonRouter<API, E> for () {
-  fn router() -> Router<E> {
+  fn router(_config: &PostJsonConfig) -> Router<E> {
     Router::new()
   }
 }
+
+impl IntoResponse for Reply {
+  fn into_response(self) -> Response {
+    let status = StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    let mut res = (status, [(header::CONTENT_TYPE, self.content_type)], self.body).into_response();
+    if let Some(secs) = self.retry_after {
+      res.headers_mut().insert(header::RETRY_AFTER, secs.into());
+    }
+    res
+  }
+}
@@ -12,10 +12,10 @@ pub type Err = String;
 
 pub type Res<A> = Result<A, Err>;
 
-#[derive(Clone, Serialize, Deserialize, JsonSchema, TS)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, TS)]
 pub struct GetA;
 
-#[derive(Clone, Serialize, Deserialize, JsonSchema, TS)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, TS)]
 pub struct PostA(pub bool);
 
 /// Some example api